@@ -258,6 +258,95 @@ fn bench_signvec_comparison(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_signvec_shift(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SignVec_Shift");
+    group.noise_threshold(0.05);
+    group.sampling_mode(criterion::SamplingMode::Flat);
+    group.warm_up_time(std::time::Duration::from_secs(5));
+
+    // Positional inserts and removes shift every index after the touched position, so they are
+    // measured near the front and in the middle of a large vector, next to a plain `Vec` doing
+    // the same.
+    let mut rng = WyRand::new();
+    let data: Vec<i32> = (0..1_000_000)
+        .map(|_| rng.generate_range(-5000i32..=5000))
+        .collect();
+    let mut sign_vec: SignVec<i32> = data.iter().collect();
+    let mut vec = data.clone();
+
+    for at in [0usize, 1000, 500_000] {
+        group.bench_with_input(BenchmarkId::new("insert_remove_svec", at), &at, |b, &at| {
+            b.iter(|| {
+                sign_vec.insert(black_box(at), black_box(-7));
+                black_box(sign_vec.remove(black_box(at)));
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("insert_remove_vec", at), &at, |b, &at| {
+            b.iter(|| {
+                vec.insert(black_box(at), black_box(-7));
+                black_box(vec.remove(black_box(at)));
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_signvec_sync(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SignVec_Sync");
+    group.noise_threshold(0.05);
+    group.sampling_mode(criterion::SamplingMode::Flat);
+    group.sample_size(10);
+
+    // Full rebuilds of the index sets over large snapshots: `sync` for each of the primitive
+    // types with a bulk classification path, `extend_from_slice` into an empty vector and, with
+    // the `rayon` feature, `par_sync`.
+    let mut rng = WyRand::new();
+    let data: Vec<i32> = (0..10_000_000)
+        .map(|_| rng.generate_range(-5000i32..=5000))
+        .collect();
+    let mut sv_i32: SignVec<i32> = data.iter().collect();
+    let mut sv_f32: SignVec<f32> = data.iter().map(|&x| x as f32).collect();
+    let mut sv_f64: SignVec<f64> = data.iter().map(|&x| x as f64).collect();
+
+    group.bench_function("sync_i32", |b| b.iter(|| sv_i32.sync()));
+    group.bench_function("sync_f32", |b| b.iter(|| sv_f32.sync()));
+    group.bench_function("sync_f64", |b| b.iter(|| sv_f64.sync()));
+
+    group.bench_function("extend_from_slice_i32", |b| {
+        b.iter(|| {
+            let mut sign_vec = SignVec::<i32>::with_capacity(data.len());
+            sign_vec.extend_from_slice(black_box(&data));
+            sign_vec
+        })
+    });
+
+    // A timestep updating every element, of which few change sign: reclassifying as the sweep
+    // goes, against writing the elements first and syncing afterwards. The step alternates so
+    // that the values do not drift between iterations.
+    let mut step = 0.5;
+    group.bench_function("update_all_f64", |b| {
+        b.iter(|| {
+            step = -step;
+            sv_f64.update_all(|_, x| *x += step)
+        })
+    });
+    group.bench_function("mutate_then_sync_f64", |b| {
+        b.iter(|| {
+            step = -step;
+            let mut bulk = sv_f64.begin_bulk();
+            bulk.slice_mut(..).iter_mut().for_each(|x| *x += step);
+            bulk.commit().unwrap();
+        })
+    });
+
+    #[cfg(feature = "rayon")]
+    group.bench_function("par_sync_f64", |b| b.iter(|| sv_f64.par_sync()));
+
+    group.finish();
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default()
@@ -266,7 +355,7 @@ criterion_group! {
         .confidence_level(0.95)
         .significance_level(0.05)
         .configure_from_args();
-    targets = bench_signvec_operations, bench_signvec_comparison
+    targets = bench_signvec_operations, bench_signvec_comparison, bench_signvec_shift, bench_signvec_sync
 }
 
 criterion_main!(benches);
@@ -0,0 +1,188 @@
+//! # signvec-derive
+//!
+//! `#[derive(Signable)]` for the [`signvec`](https://docs.rs/signvec) crate. Enable the `derive`
+//! feature of `signvec` rather than depending on this crate directly.
+//!
+//! - On a struct, `#[signable(field = "name")]` delegates to the named field. Tuple structs use
+//!   the field index, as in `#[signable(field = "0")]`, and a struct with a single field
+//!   delegates to it without any attribute.
+//! - On an enum, every variant carries one of `#[signable(plus)]`, `#[signable(minus)]` or
+//!   `#[signable(zero)]`.
+//!
+//! ```ignore
+//! use signvec::Signable;
+//!
+//! #[derive(Clone, Signable)]
+//! #[signable(field = "balance")]
+//! struct Account {
+//!     id: u32,
+//!     balance: i64,
+//! }
+//!
+//! #[derive(Clone, Signable)]
+//! enum Spin {
+//!     #[signable(plus)]
+//!     Up,
+//!     #[signable(minus)]
+//!     Down,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DataStruct, DeriveInput, Error,
+    Fields, Index, LitStr, Member, Result,
+};
+
+#[proc_macro_derive(Signable, attributes(signable))]
+pub fn derive_signable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2> {
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (member, ty) = delegate(&input.attrs, data, &input)?;
+            input
+                .generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#ty: ::signvec::Signable));
+            quote! {
+                #[inline(always)]
+                fn sign(&self) -> ::signvec::Sign {
+                    ::signvec::Signable::sign(&self.#member)
+                }
+
+                #[inline(always)]
+                fn is_nan(&self) -> bool {
+                    ::signvec::Signable::is_nan(&self.#member)
+                }
+            }
+        }
+        Data::Enum(data) => {
+            let arms = variant_arms(data)?;
+            quote! {
+                #[inline(always)]
+                fn sign(&self) -> ::signvec::Sign {
+                    match *self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Signable cannot be derived for unions",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::signvec::Signable for #name #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+/// Finds the field a struct delegates to, returning it along with its type.
+fn delegate<'a>(
+    attrs: &[Attribute],
+    data: &'a DataStruct,
+    input: &DeriveInput,
+) -> Result<(Member, &'a syn::Type)> {
+    let mut name: Option<LitStr> = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("signable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("field") {
+                name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `field = \"...\"`"))
+            }
+        })?;
+    }
+
+    let fields: Vec<_> = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let Some(name) = name else {
+        return match fields.as_slice() {
+            [field] => Ok((
+                match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(Index::from(0)),
+                },
+                &field.ty,
+            )),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                "expected #[signable(field = \"...\")] on a struct without exactly one field",
+            )),
+        };
+    };
+
+    let value = name.value();
+    fields
+        .iter()
+        .enumerate()
+        .find_map(|(i, field)| match &field.ident {
+            Some(ident) if *ident == value => Some((Member::Named(ident.clone()), &field.ty)),
+            None if value == i.to_string() => Some((Member::Unnamed(Index::from(i)), &field.ty)),
+            _ => None,
+        })
+        .ok_or_else(|| Error::new(name.span(), format!("no field named `{}`", value)))
+}
+
+/// Builds one match arm per variant from its `plus`, `minus` or `zero` attribute.
+fn variant_arms(data: &DataEnum) -> Result<Vec<TokenStream2>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            let mut sign = None;
+            for attr in variant
+                .attrs
+                .iter()
+                .filter(|a| a.path().is_ident("signable"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    let variant = if meta.path.is_ident("plus") {
+                        quote!(Plus)
+                    } else if meta.path.is_ident("minus") {
+                        quote!(Minus)
+                    } else if meta.path.is_ident("zero") {
+                        quote!(Zero)
+                    } else {
+                        return Err(meta.error("expected `plus`, `minus` or `zero`"));
+                    };
+                    if sign.replace(variant).is_some() {
+                        return Err(meta.error("a variant can only have one sign"));
+                    }
+                    Ok(())
+                })?;
+            }
+            let sign = sign.ok_or_else(|| {
+                Error::new_spanned(
+                    &variant.ident,
+                    "expected #[signable(plus)], #[signable(minus)] or #[signable(zero)]",
+                )
+            })?;
+            let ident = &variant.ident;
+            Ok(quote_spanned! {variant.span()=>
+                Self::#ident { .. } => ::signvec::Sign::#sign,
+            })
+        })
+        .collect()
+}
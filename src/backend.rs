@@ -0,0 +1,102 @@
+use nanorand::WyRand;
+use std::fmt;
+
+/// Storage for the index sets of a `SignVec`.
+///
+/// A `SignVec<T, S>` keeps one `S` per bucket. Besides the usual set operations, a backend has
+/// to renumber its members when an element is inserted into or removed from the middle of the
+/// vector; `SignVec` relies on these to keep positional updates from rebuilding the sets.
+///
+/// Two backends are provided:
+///
+/// * `IndexSet`, the default, with O(1) updates and random selection and O(√n) positional
+///   shifts, amortized over the occasional resizing of its blocks, whose memory grows with the
+///   largest index it holds. `IndexSet<u32>` stores the indices in half the space, for vectors
+///   shorter than `u32::MAX` elements.
+/// * `BitmapIndex`, with one bit per element and a rank directory, using about `n / 8` bytes for
+///   a vector of length `n`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{BitmapIndex, Sign, SignVec};
+///
+/// let mut sign_vec: SignVec<i32, BitmapIndex> = vec![5, -10, 15].into_iter().collect();
+/// sign_vec.insert(0, -1);
+///
+/// assert_eq!(sign_vec.count(Sign::Minus), 2);
+/// assert!(sign_vec.indices(Sign::Minus).contains(&0));
+/// ```
+pub trait IndexBackend: Default {
+    /// The iterator over the members of the set.
+    type Iter<'a>: Iterator<Item = usize> + fmt::Debug
+    where
+        Self: 'a;
+
+    /// The largest vector length whose indices the backend can store.
+    const MAX_LEN: usize = usize::MAX;
+
+    /// Creates an empty set with room for indices up to `max_element`.
+    fn with_max(max_element: usize) -> Self;
+
+    /// Returns the number of indices in the set.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the set holds no indices.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if `value` is in the set.
+    fn contains(&self, value: &usize) -> bool;
+
+    /// Adds `value` to the set, returning `false` if it was already present.
+    fn insert(&mut self, value: usize) -> bool;
+
+    /// Adds `base + i` to the set for each set bit `i` of `mask`.
+    fn insert_mask(&mut self, base: usize, mut mask: u64) {
+        while mask != 0 {
+            self.insert(base + mask.trailing_zeros() as usize);
+            mask &= mask - 1;
+        }
+    }
+
+    /// Removes `value` from the set, returning `false` if it was not present.
+    fn remove(&mut self, value: &usize) -> bool;
+
+    /// Removes every index from the set.
+    fn clear(&mut self);
+
+    /// Returns an iterator over the indices in the set.
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// Returns a uniformly chosen index from the set, or `None` if it is empty.
+    fn random(&self, rng: &mut WyRand) -> Option<usize>;
+
+    /// Returns the number of indices, counting from zero, the set has room for without
+    /// reallocating.
+    fn capacity(&self) -> usize;
+
+    /// Reserves room for indices up to `new_max_element`.
+    fn reserve(&mut self, new_max_element: usize);
+
+    /// Shrinks the set's storage, keeping room for indices below `min_capacity`.
+    fn shrink_to(&mut self, min_capacity: usize);
+
+    /// Shrinks the set's storage as much as possible.
+    fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Renumbers the set for an element inserted at `at`: every index at or above `at` moves up
+    /// by one.
+    fn insert_shift(&mut self, at: usize);
+
+    /// Renumbers the set for the element at `at` being removed: `at` leaves the set and every
+    /// index above it moves down by one. Returns whether `at` was a member.
+    fn remove_shift(&mut self, at: usize) -> bool;
+
+    /// Renumbers the set for the elements in `start..end` being removed: those indices leave the
+    /// set and every index at or above `end` moves down by `end - start`.
+    fn remove_range_shift(&mut self, start: usize, end: usize);
+}
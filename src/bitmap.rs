@@ -0,0 +1,641 @@
+use crate::IndexBackend;
+use fastset::Set;
+use nanorand::{Rng, WyRand};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The number of words summarized by one entry of the rank directory.
+const BLOCK_WORDS: usize = 8;
+
+/// A compact index set holding one bit per element, for use as the backend of a `SignVec`.
+///
+/// Next to the bits it keeps a rank directory, a Fenwick tree over the number of members in
+/// each block of 512 indices, so that `len` is O(1) and `rank`, `select` and `random` are
+/// O(log n). Tracking a vector of length `n` takes about `n / 8` bytes per set, independent of
+/// how the elements are spread over the buckets, and renumbering on positional inserts and
+/// removes is a bit shift.
+///
+/// Members are iterated in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{BitmapIndex, Sign, SignVec};
+///
+/// let sign_vec: SignVec<f64, BitmapIndex> = [1.0, -2.0, 3.0].into_iter().collect();
+/// let pos = sign_vec.indices(Sign::Plus);
+///
+/// assert_eq!(pos.iter().collect::<Vec<_>>(), vec![0, 2]);
+/// assert_eq!(pos.rank(2), 1);
+/// assert_eq!(pos.select(1), Some(2));
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<usize>", into = "Vec<usize>")]
+pub struct BitmapIndex {
+    words: Vec<u64>,
+    /// Fenwick tree over the member counts of the blocks of `BLOCK_WORDS` words, stored with
+    /// node `i` (1-based) at position `i - 1`.
+    tree: Vec<usize>,
+    len: usize,
+}
+
+impl BitmapIndex {
+    /// Creates a new, empty `BitmapIndex`.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty `BitmapIndex` with room for indices up to `max_element`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::BitmapIndex;
+    ///
+    /// let mut set = BitmapIndex::with_max(1000);
+    /// set.insert(1000);
+    /// assert!(set.contains(&1000));
+    /// ```
+    #[inline(always)]
+    pub fn with_max(max_element: usize) -> Self {
+        let mut set = Self::default();
+        set.reserve(max_element);
+        set
+    }
+
+    /// Returns the number of indices in the set.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set holds no indices.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if `value` is in the set.
+    #[inline(always)]
+    pub fn contains(&self, value: &usize) -> bool {
+        self.words
+            .get(value / 64)
+            .is_some_and(|word| word & (1 << (value % 64)) != 0)
+    }
+
+    /// Adds `value` to the set, returning `false` if it was already present.
+    #[inline(always)]
+    pub fn insert(&mut self, value: usize) -> bool {
+        if value / 64 >= self.words.len() {
+            // Grow geometrically, so that pushing at the end stays amortized O(1).
+            self.grow((value / 64 + 1).max(self.words.len() * 2));
+        }
+        let word = &mut self.words[value / 64];
+        let bit = 1 << (value % 64);
+        if *word & bit != 0 {
+            return false;
+        }
+        *word |= bit;
+        self.len += 1;
+        self.add(value / 64 / BLOCK_WORDS, 1);
+        true
+    }
+
+    /// Adds `base + i` to the set for each set bit `i` of `mask`, a word at a time.
+    #[inline(always)]
+    pub fn insert_mask(&mut self, base: usize, mask: u64) {
+        let (word, shift) = (base / 64, base % 64);
+        self.or_word(word, mask << shift);
+        if shift != 0 {
+            self.or_word(word + 1, mask >> (64 - shift));
+        }
+    }
+
+    /// Removes `value` from the set, returning `false` if it was not present.
+    #[inline(always)]
+    pub fn remove(&mut self, value: &usize) -> bool {
+        let Some(word) = self.words.get_mut(value / 64) else {
+            return false;
+        };
+        let bit = 1 << (value % 64);
+        if *word & bit == 0 {
+            return false;
+        }
+        *word &= !bit;
+        self.len -= 1;
+        self.sub(value / 64 / BLOCK_WORDS, 1);
+        true
+    }
+
+    /// Removes every index from the set.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+        self.tree.fill(0);
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the indices in the set, in ascending order.
+    #[inline(always)]
+    pub fn iter(&self) -> BitmapIter<'_> {
+        BitmapIter {
+            words: &self.words,
+            next_word: 0,
+            base: 0,
+            current: 0,
+        }
+    }
+
+    /// Returns the number of members below `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::BitmapIndex;
+    ///
+    /// let set = BitmapIndex::from([1, 600, 700]);
+    /// assert_eq!(set.rank(0), 0);
+    /// assert_eq!(set.rank(600), 1);
+    /// assert_eq!(set.rank(5000), 3);
+    /// ```
+    pub fn rank(&self, value: usize) -> usize {
+        let word = value / 64;
+        if word >= self.words.len() {
+            return self.len;
+        }
+        let block = word / BLOCK_WORDS;
+        let mut rank = self.prefix(block);
+        rank += self.words[block * BLOCK_WORDS..word]
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        rank + (self.words[word] & ((1 << (value % 64)) - 1)).count_ones() as usize
+    }
+
+    /// Returns the `n`th smallest member, counting from zero, or `None` if the set has no more
+    /// than `n` members.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::BitmapIndex;
+    ///
+    /// let set = BitmapIndex::from([700, 1, 600]);
+    /// assert_eq!(set.select(0), Some(1));
+    /// assert_eq!(set.select(2), Some(700));
+    /// assert_eq!(set.select(3), None);
+    /// ```
+    pub fn select(&self, n: usize) -> Option<usize> {
+        if n >= self.len {
+            return None;
+        }
+        // Descend the Fenwick tree to the block holding the member.
+        let mut block = 0;
+        let mut rest = n;
+        let mut step = self.tree.len().checked_next_power_of_two()?;
+        while step > 0 {
+            if block + step <= self.tree.len() && self.tree[block + step - 1] <= rest {
+                block += step;
+                rest -= self.tree[block - 1];
+            }
+            step /= 2;
+        }
+        for (i, &word) in self.words[block * BLOCK_WORDS..].iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if rest < ones {
+                let mut word = word;
+                for _ in 0..rest {
+                    word &= word - 1;
+                }
+                return Some((block * BLOCK_WORDS + i) * 64 + word.trailing_zeros() as usize);
+            }
+            rest -= ones;
+        }
+        unreachable!("rank directory out of sync with the bitmap")
+    }
+
+    /// Returns a uniformly chosen index from the set, or `None` if it is empty.
+    #[inline(always)]
+    pub fn random(&self, rng: &mut WyRand) -> Option<usize> {
+        match self.len {
+            0 => None,
+            len => self.select(rng.generate_range(0..len)),
+        }
+    }
+
+    /// Returns the number of indices, counting from zero, the bitmap has room for without
+    /// growing.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.words.len() * 64
+    }
+
+    /// Reserves room for indices up to `new_max_element`.
+    #[inline(always)]
+    pub fn reserve(&mut self, new_max_element: usize) {
+        let words = new_max_element / 64 + 1;
+        if words > self.words.len() {
+            self.grow(words);
+        }
+    }
+
+    /// Shrinks the bitmap as much as possible while keeping room for indices below
+    /// `min_capacity`.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let used = self
+            .words
+            .iter()
+            .rposition(|&w| w != 0)
+            .map_or(0, |i| i + 1);
+        self.words.truncate(used.max(min_capacity.div_ceil(64)));
+        self.words.shrink_to_fit();
+        self.rebuild();
+        self.tree.shrink_to_fit();
+    }
+
+    /// Shrinks the bitmap as much as possible.
+    #[inline(always)]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Extends the bitmap with empty words up to `words` words.
+    fn grow(&mut self, words: usize) {
+        self.words.resize(words, 0);
+        let blocks = words.div_ceil(BLOCK_WORDS);
+        // A new node covers only empty blocks of its own plus the nodes below it.
+        for node in self.tree.len() + 1..=blocks {
+            let mut sum = 0;
+            let mut child = node - 1;
+            while child > node - lowbit(node) {
+                sum += self.tree[child - 1];
+                child -= lowbit(child);
+            }
+            self.tree.push(sum);
+        }
+    }
+
+    /// Recomputes the rank directory from the bits.
+    fn rebuild(&mut self) {
+        let blocks = self.words.len().div_ceil(BLOCK_WORDS);
+        self.tree.clear();
+        self.tree.extend(
+            self.words
+                .chunks(BLOCK_WORDS)
+                .map(|chunk| chunk.iter().map(|w| w.count_ones() as usize).sum::<usize>()),
+        );
+        for node in 1..=blocks {
+            let parent = node + lowbit(node);
+            if parent <= blocks {
+                self.tree[parent - 1] += self.tree[node - 1];
+            }
+        }
+    }
+
+    /// Returns the number of members in the blocks before `block`.
+    #[inline(always)]
+    fn prefix(&self, block: usize) -> usize {
+        let mut sum = 0;
+        let mut node = block;
+        while node > 0 {
+            sum += self.tree[node - 1];
+            node -= lowbit(node);
+        }
+        sum
+    }
+
+    #[inline(always)]
+    fn add(&mut self, block: usize, count: usize) {
+        let mut node = block + 1;
+        while node <= self.tree.len() {
+            self.tree[node - 1] += count;
+            node += lowbit(node);
+        }
+    }
+
+    #[inline(always)]
+    fn sub(&mut self, block: usize, count: usize) {
+        let mut node = block + 1;
+        while node <= self.tree.len() {
+            self.tree[node - 1] -= count;
+            node += lowbit(node);
+        }
+    }
+
+    /// Sets `bits` in word `index`, keeping the count and the rank directory in step.
+    #[inline(always)]
+    fn or_word(&mut self, index: usize, bits: u64) {
+        if bits == 0 {
+            return;
+        }
+        if index >= self.words.len() {
+            self.grow((index + 1).max(self.words.len() * 2));
+        }
+        let new = bits & !self.words[index];
+        if new != 0 {
+            self.words[index] |= new;
+            self.len += new.count_ones() as usize;
+            self.add(index / BLOCK_WORDS, new.count_ones() as usize);
+        }
+    }
+
+    /// Returns the 64 bits starting at bit `pos`, reading zeros past the end.
+    #[inline(always)]
+    fn bits_at(&self, pos: usize) -> u64 {
+        let (word, shift) = (pos / 64, pos % 64);
+        let low = self.words.get(word).map_or(0, |w| w >> shift);
+        let high = match shift {
+            0 => 0,
+            _ => self.words.get(word + 1).map_or(0, |w| w << (64 - shift)),
+        };
+        low | high
+    }
+}
+
+#[inline(always)]
+fn lowbit(node: usize) -> usize {
+    node & node.wrapping_neg()
+}
+
+impl IndexBackend for BitmapIndex {
+    type Iter<'a> = BitmapIter<'a>;
+
+    #[inline(always)]
+    fn with_max(max_element: usize) -> Self {
+        BitmapIndex::with_max(max_element)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn contains(&self, value: &usize) -> bool {
+        BitmapIndex::contains(self, value)
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, value: usize) -> bool {
+        BitmapIndex::insert(self, value)
+    }
+
+    #[inline(always)]
+    fn insert_mask(&mut self, base: usize, mask: u64) {
+        BitmapIndex::insert_mask(self, base, mask)
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, value: &usize) -> bool {
+        BitmapIndex::remove(self, value)
+    }
+
+    #[inline(always)]
+    fn clear(&mut self) {
+        BitmapIndex::clear(self)
+    }
+
+    #[inline(always)]
+    fn iter(&self) -> Self::Iter<'_> {
+        BitmapIndex::iter(self)
+    }
+
+    #[inline(always)]
+    fn random(&self, rng: &mut WyRand) -> Option<usize> {
+        BitmapIndex::random(self, rng)
+    }
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        BitmapIndex::capacity(self)
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, new_max_element: usize) {
+        BitmapIndex::reserve(self, new_max_element)
+    }
+
+    #[inline(always)]
+    fn shrink_to(&mut self, min_capacity: usize) {
+        BitmapIndex::shrink_to(self, min_capacity)
+    }
+
+    fn insert_shift(&mut self, at: usize) {
+        let first = at / 64;
+        if first >= self.words.len() {
+            return;
+        }
+        if self.words[self.words.len() - 1] >> 63 != 0 {
+            let words = self.words.len() + 1;
+            self.grow(words);
+        }
+        for i in (first + 1..self.words.len()).rev() {
+            self.words[i] = (self.words[i] << 1) | (self.words[i - 1] >> 63);
+        }
+        let keep = (1 << (at % 64)) - 1;
+        let word = self.words[first];
+        self.words[first] = (word & keep) | ((word & !keep) << 1);
+        self.rebuild();
+    }
+
+    fn remove_shift(&mut self, at: usize) -> bool {
+        let removed = self.contains(&at);
+        self.remove_range_shift(at, at + 1);
+        removed
+    }
+
+    fn remove_range_shift(&mut self, start: usize, end: usize) {
+        let first = start / 64;
+        if start >= end || first >= self.words.len() {
+            return;
+        }
+        let removed = self.rank(end) - self.rank(start);
+        let width = end - start;
+        let keep = (1 << (start % 64)) - 1;
+        let shifted = self.bits_at(first * 64 + width);
+        self.words[first] = (self.words[first] & keep) | (shifted & !keep);
+        for i in first + 1..self.words.len() {
+            // Bits are only read from at or after word `i`, which have not been overwritten yet.
+            self.words[i] = self.bits_at(i * 64 + width);
+        }
+        self.len -= removed;
+        self.rebuild();
+    }
+}
+
+/// An iterator over the members of a `BitmapIndex`, in ascending order.
+#[derive(Debug, Clone)]
+pub struct BitmapIter<'a> {
+    words: &'a [u64],
+    next_word: usize,
+    base: usize,
+    current: u64,
+}
+
+impl Iterator for BitmapIter<'_> {
+    type Item = usize;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.current = *self.words.get(self.next_word)?;
+            self.base = self.next_word * 64;
+            self.next_word += 1;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.base + bit)
+    }
+}
+
+impl fmt::Debug for BitmapIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Two bitmaps are equal if they hold the same indices, irrespective of their capacity.
+impl PartialEq for BitmapIndex {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for BitmapIndex {}
+
+impl PartialEq<Set> for BitmapIndex {
+    fn eq(&self, other: &Set) -> bool {
+        self.len == other.len() && self.iter().all(|value| other.contains(&value))
+    }
+}
+
+impl From<Vec<usize>> for BitmapIndex {
+    fn from(values: Vec<usize>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for BitmapIndex {
+    fn from(values: [usize; N]) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl From<BitmapIndex> for Vec<usize> {
+    fn from(set: BitmapIndex) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl Extend<usize> for BitmapIndex {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl FromIterator<usize> for BitmapIndex {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a BitmapIndex {
+    type Item = usize;
+    type IntoIter = BitmapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Sign, SignVec, Signable};
+    use fastset::set;
+
+    #[test]
+    fn test_rank_select() {
+        let values = [3, 64, 65, 511, 512, 4000, 70_000];
+        let mut set = BitmapIndex::from(values);
+        assert_eq!(set.len(), values.len());
+        for (n, &value) in values.iter().enumerate() {
+            assert_eq!(set.select(n), Some(value));
+            assert_eq!(set.rank(value), n);
+        }
+        assert!(set.remove(&512));
+        assert!(!set.remove(&512));
+        assert_eq!(set.select(4), Some(4000));
+        assert_eq!(set.rank(70_001), 6);
+        set.clear();
+        assert_eq!(set.select(0), None);
+        assert!(set.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_insert_mask() {
+        let mut set = BitmapIndex::from([70]);
+        set.insert_mask(60, (1 << 10) | (1 << 63) | 0b11);
+        assert_eq!(set, set![60, 61, 70, 123]);
+        set.insert_mask(128, u64::MAX);
+        assert_eq!(set.len(), 68);
+        assert_eq!(set.rank(192), 68);
+        assert_eq!(set.select(4), Some(128));
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut set = BitmapIndex::from([0, 63, 64, 600]);
+        set.insert_shift(63);
+        assert_eq!(set, set![0, 64, 65, 601]);
+        assert!(set.remove_shift(64));
+        assert_eq!(set, set![0, 64, 600]);
+        set.remove_range_shift(1, 100);
+        assert_eq!(set, set![0, 501]);
+        assert_eq!(set.select(1), Some(501));
+
+        let mut set = BitmapIndex::from([63]);
+        set.insert_shift(0);
+        assert_eq!(set, set![64]);
+        assert_eq!(set.rank(65), 1);
+    }
+
+    #[test]
+    fn test_signvec_backend() {
+        let mut rng = WyRand::new_seed(42);
+        let mut sv: SignVec<i32, BitmapIndex> = (0..2000).map(|i| (i % 7) - 3).collect();
+        let mut vals: Vec<i32> = sv.to_vec();
+        for step in 0..300 {
+            let at = rng.generate_range(0..vals.len());
+            let val = rng.generate_range(-2i32..=2);
+            match step % 3 {
+                0 => {
+                    sv.insert(at, val);
+                    vals.insert(at, val);
+                }
+                1 => assert_eq!(sv.remove(at), vals.remove(at)),
+                _ => {
+                    sv.set(at, val);
+                    vals[at] = val;
+                }
+            }
+        }
+        sv.drain(100..400);
+        vals.drain(100..400);
+        assert_eq!(sv.as_slice(), vals.as_slice());
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            let expected: Vec<_> = (0..vals.len())
+                .filter(|&i| vals[i].sign() == sign)
+                .collect();
+            assert_eq!(sv.indices(sign).iter().collect::<Vec<_>>(), expected);
+            assert_eq!(sv.count(sign), expected.len());
+        }
+        let idx = sv.random(Sign::Minus, &mut rng).unwrap();
+        assert!(vals[idx] < 0);
+    }
+}
@@ -0,0 +1,701 @@
+use crate::{IndexBackend, IndexSet, Sign, Signable};
+use nanorand::WyRand;
+use std::ops::Deref;
+
+/// Trait for types that can be sorted into one of `K` categories.
+///
+/// `category` must return a value below `K`; `CategoryVec` panics otherwise.
+///
+/// The signed integers and floats are `Categorize<3>`, with `Sign::Plus`, `Sign::Minus` and
+/// `Sign::Zero` mapping to categories `0`, `1` and `2`, so a `CategoryVec<T, 3>` mirrors the sign
+/// buckets of a `SignVec<T>`. NaNs are categorized by their sign bit. Other `Signable` types are
+/// categorized the same way when wrapped in `SignCategory`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{Categorize, CategoryVec};
+///
+/// #[derive(Clone)]
+/// enum Spin {
+///     Up,
+///     Down,
+///     Left,
+///     Right,
+/// }
+///
+/// impl Categorize<4> for Spin {
+///     fn category(&self) -> usize {
+///         match self {
+///             Spin::Up => 0,
+///             Spin::Down => 1,
+///             Spin::Left => 2,
+///             Spin::Right => 3,
+///         }
+///     }
+/// }
+///
+/// let spins: CategoryVec<Spin, 4> = vec![Spin::Up, Spin::Left, Spin::Up].into();
+/// assert_eq!(spins.count(0), 2);
+/// assert_eq!(spins.count(2), 1);
+/// ```
+pub trait Categorize<const K: usize> {
+    fn category(&self) -> usize;
+}
+
+/// Returns the category of a value of sign `sign` among three.
+#[inline(always)]
+fn sign_category(sign: Sign) -> usize {
+    match sign {
+        Sign::Plus => 0,
+        Sign::Minus => 1,
+        Sign::Zero => 2,
+    }
+}
+
+macro_rules! categorize_sign {
+    ($($t:ty),*) => {$(
+        impl Categorize<3> for $t {
+            #[inline(always)]
+            fn category(&self) -> usize {
+                sign_category(self.sign())
+            }
+        }
+    )*};
+}
+
+categorize_sign!(i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// Wrapper categorizing a `Signable` value by its sign, as `Categorize<3>`.
+///
+/// `Sign::Plus`, `Sign::Minus` and `Sign::Zero` map to categories `0`, `1` and `2`, as for the
+/// primitive numbers. Types that are both `Signable` and categorized some other way can still
+/// implement `Categorize<3>` themselves.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{CategoryVec, SignBit, SignCategory};
+///
+/// let cv: CategoryVec<_, 3> = vec![SignCategory(SignBit(-0.0)), SignCategory(SignBit(1.0))].into();
+///
+/// assert_eq!(cv.count(0), 1);
+/// assert_eq!(cv.count(1), 1);
+/// assert_eq!(cv.count(2), 0);
+/// ```
+#[derive(Debug, Default, PartialEq, PartialOrd, Copy, Clone)]
+pub struct SignCategory<T>(pub T);
+
+impl<T> SignCategory<T> {
+    /// Unwraps the inner value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Categorize<3> for SignCategory<T>
+where
+    T: Signable,
+{
+    #[inline(always)]
+    fn category(&self) -> usize {
+        sign_category(self.0.sign())
+    }
+}
+
+/// A vector-like data structure tracking which of `K` categories each element belongs to.
+///
+/// This generalizes `SignVec` to any number of buckets: `sets[k]` holds the indices of the
+/// elements whose `Categorize::category` is `k`. Counting, index lookup and random selection
+/// by category are O(1), and `set` moves an index between two sets without touching the others.
+///
+/// # Type Parameters
+///
+/// * `T`: The type of elements stored in the `CategoryVec`, which must implement
+///   `Categorize<K>` and also be cloneable.
+/// * `K`: The number of categories.
+/// * `S`: The `IndexBackend` storing the index set of each category, `IndexSet` by default.
+#[derive(Debug, Clone)]
+pub struct CategoryVec<T, const K: usize, S = IndexSet>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    vals: Vec<T>,
+    sets: [S; K],
+}
+
+impl<T, const K: usize, S> CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    /// Returns a slice containing all elements in the `CategoryVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 0].into();
+    ///
+    /// assert_eq!(cv.as_slice(), &[1, -2, 0]);
+    /// ```
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[T] {
+        self.vals.as_slice()
+    }
+
+    /// Returns the category of the element at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 0].into();
+    ///
+    /// assert_eq!(cv.category(1), 1);
+    /// ```
+    #[inline(always)]
+    pub fn category(&self, idx: usize) -> usize {
+        self.vals[idx].category()
+    }
+
+    /// Clears the `CategoryVec`, removing all values and emptying every category set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 0].into();
+    /// cv.clear();
+    ///
+    /// assert!(cv.is_empty());
+    /// assert_eq!(cv.count(0), 0);
+    /// ```
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.vals.clear();
+        self.sets.iter_mut().for_each(S::clear);
+    }
+
+    /// Returns the number of elements in category `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    ///
+    /// assert_eq!(cv.count(0), 2);
+    /// assert_eq!(cv.count(1), 1);
+    /// ```
+    #[inline(always)]
+    pub fn count(&self, k: usize) -> usize {
+        self.sets[k].len()
+    }
+
+    /// Returns a reference to the set of indices of elements in category `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{CategoryVec, IndexSet};
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    ///
+    /// assert_eq!(cv.indices(0), &IndexSet::from([0, 2]));
+    /// ```
+    #[inline(always)]
+    pub fn indices(&self, k: usize) -> &S {
+        &self.sets[k]
+    }
+
+    /// Removes the last element and returns it, or `None` if the `CategoryVec` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2].into();
+    ///
+    /// assert_eq!(cv.pop(), Some(-2));
+    /// assert_eq!(cv.count(1), 0);
+    /// ```
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<T> {
+        let popped = self.vals.pop()?;
+        let idx = self.vals.len();
+        self.sets[popped.category()].remove(&idx);
+        Some(popped)
+    }
+
+    /// Appends an element to the back of the `CategoryVec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element's category is not below `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv = CategoryVec::<i32, 3>::new();
+    /// cv.push(0);
+    ///
+    /// assert_eq!(cv.count(2), 1);
+    /// ```
+    #[inline(always)]
+    pub fn push(&mut self, element: T) {
+        let k = Self::checked_category(&element);
+        self.sets[k].insert(self.vals.len());
+        self.vals.push(element);
+    }
+
+    /// Returns a random index of an element in category `k`, or `None` if the category is
+    /// empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`: The category to pick from.
+    /// * `rng`: A mutable reference to a random number generator implementing the `WyRand` trait.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    /// use nanorand::WyRand;
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    /// let mut rng = WyRand::new();
+    ///
+    /// assert_eq!(cv.random(1, &mut rng), Some(1));
+    /// ```
+    #[inline(always)]
+    pub fn random(&self, k: usize, rng: &mut WyRand) -> Option<usize> {
+        self.sets[k].random(rng)
+    }
+
+    /// Sets the value at the specified index, moving the index to its new category if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or the value's category is not below `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    /// cv.set(0, 0);
+    ///
+    /// assert_eq!(cv.count(0), 1);
+    /// assert_eq!(cv.count(2), 1);
+    /// ```
+    #[inline(always)]
+    pub fn set(&mut self, idx: usize, val: T) {
+        if idx >= self.vals.len() {
+            panic!(
+                "Index out of bounds: index {} length {}",
+                idx,
+                self.vals.len()
+            );
+        }
+        // Safety: We've verified that idx is within bounds above
+        unsafe {
+            self.set_unchecked(idx, val);
+        }
+    }
+
+    /// Sets the value at the specified index without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an out-of-bounds index is undefined behavior.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value's category is not below `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    /// unsafe {
+    ///     cv.set_unchecked(1, 4);
+    /// }
+    ///
+    /// assert_eq!(cv.count(0), 3);
+    /// ```
+    #[inline(always)]
+    pub unsafe fn set_unchecked(&mut self, idx: usize, val: T) {
+        let new_k = Self::checked_category(&val);
+        let old_val = &mut *self.vals.as_mut_ptr().add(idx);
+        let old_k = old_val.category();
+        *old_val = val;
+        if old_k != new_k {
+            self.sets[old_k].remove(&idx);
+            self.sets[new_k].insert(idx);
+        }
+    }
+
+    /// Removes an element from the `CategoryVec` by swapping it with the last element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{CategoryVec, IndexSet};
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    ///
+    /// assert_eq!(cv.swap_remove(0), 1);
+    /// assert_eq!(cv.indices(0), &IndexSet::from([0]));
+    /// ```
+    #[inline(always)]
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let removed = self.vals.swap_remove(index);
+        self.sets[removed.category()].remove(&index);
+        let last = self.vals.len();
+        if index < last {
+            let set = &mut self.sets[self.vals[index].category()];
+            set.remove(&last);
+            set.insert(index);
+        }
+        removed
+    }
+
+    /// Rebuilds every category set from the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    /// cv.sync();
+    ///
+    /// assert_eq!(cv.count(0), 2);
+    /// ```
+    #[inline(always)]
+    pub fn sync(&mut self) {
+        self.sets.iter_mut().for_each(S::clear);
+        for (idx, val) in self.vals.iter().enumerate() {
+            self.sets[Self::checked_category(val)].insert(idx);
+        }
+    }
+
+    /// Shortens the `CategoryVec`, keeping the first `len` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let mut cv: CategoryVec<_, 3> = vec![1, -2, 0].into();
+    /// cv.truncate(1);
+    ///
+    /// assert_eq!(cv.count(1), 0);
+    /// assert_eq!(cv.count(2), 0);
+    /// ```
+    #[inline(always)]
+    pub fn truncate(&mut self, len: usize) {
+        for idx in len..self.vals.len() {
+            self.sets[self.vals[idx].category()].remove(&idx);
+        }
+        self.vals.truncate(len);
+    }
+
+    /// Returns an iterator over the values in category `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv: CategoryVec<_, 3> = vec![1, -2, 3].into();
+    ///
+    /// assert_eq!(cv.values(0).collect::<Vec<_>>(), vec![&1, &3]);
+    /// ```
+    #[inline(always)]
+    pub fn values(&self, k: usize) -> impl Iterator<Item = &T> + '_ {
+        self.sets[k].iter().map(move |idx| &self.vals[idx])
+    }
+
+    /// Creates a new empty `CategoryVec` storing its index sets in the backend `S`, with room for
+    /// `capacity` elements.
+    ///
+    /// The other constructors use the default `IndexSet` backend. A `CategoryVec` with a
+    /// different backend starts from here, or from `collect`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{BitmapIndex, CategoryVec};
+    ///
+    /// let mut cv = CategoryVec::<i32, 3, BitmapIndex>::with_backend(10);
+    /// cv.extend([1, -2, 3]);
+    ///
+    /// assert_eq!(cv.count(0), 2);
+    /// ```
+    #[inline(always)]
+    pub fn with_backend(capacity: usize) -> Self {
+        CategoryVec {
+            vals: Vec::with_capacity(capacity),
+            sets: std::array::from_fn(|_| S::with_max(capacity)),
+        }
+    }
+}
+
+impl<T, const K: usize, S> CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    /// Returns the category of `val`, checking that it is below `K`.
+    #[inline(always)]
+    fn checked_category(val: &T) -> usize {
+        let k = val.category();
+        assert!(k < K, "Category out of bounds: category {} K {}", k, K);
+        k
+    }
+}
+
+impl<T, const K: usize> CategoryVec<T, K>
+where
+    T: Categorize<K> + Clone,
+{
+    /// Creates a new empty `CategoryVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv = CategoryVec::<i32, 3>::new();
+    ///
+    /// assert!(cv.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new empty `CategoryVec` with the specified capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::CategoryVec;
+    ///
+    /// let cv = CategoryVec::<i32, 3>::with_capacity(10);
+    ///
+    /// assert!(cv.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_backend(capacity)
+    }
+}
+
+impl<T, const K: usize, S> Default for CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    fn default() -> Self {
+        CategoryVec {
+            vals: Vec::default(),
+            sets: std::array::from_fn(|_| S::default()),
+        }
+    }
+}
+
+impl<T, const K: usize, S> Deref for CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    type Target = [T];
+
+    /// Dereferences the `CategoryVec` to a slice of its values.
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.vals
+    }
+}
+
+impl<T, const K: usize, S> Extend<T> for CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, const K: usize, S> FromIterator<T> for CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut category_vec = CategoryVec::default();
+        category_vec.extend(iter);
+        category_vec
+    }
+}
+
+impl<T, const K: usize, S> From<Vec<T>> for CategoryVec<T, K, S>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    /// Converts a vector into a `CategoryVec`, moving its elements.
+    fn from(vec: Vec<T>) -> Self {
+        vec.into_iter().collect()
+    }
+}
+
+impl<T, const K: usize, S> From<CategoryVec<T, K, S>> for Vec<T>
+where
+    T: Categorize<K> + Clone,
+    S: IndexBackend,
+{
+    /// Converts a `CategoryVec` into a `Vec`, moving its elements.
+    fn from(category_vec: CategoryVec<T, K, S>) -> Self {
+        category_vec.vals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitmapIndex, SignBit};
+    use fastset::{set, Set};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Potts(usize);
+
+    impl Categorize<4> for Potts {
+        fn category(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_signable_categories() {
+        let cv: CategoryVec<_, 3> = vec![1.5, -2.0, 0.0, 3.0].into();
+        assert_eq!(cv.indices(0), &set![0, 3]);
+        assert_eq!(cv.indices(1), &set![1]);
+        assert_eq!(cv.indices(2), &set![2]);
+        assert_eq!(cv.len(), 4);
+        assert_eq!(cv[1], -2.0);
+    }
+
+    #[test]
+    fn test_sign_category() {
+        let cv: CategoryVec<_, 3> = [SignBit(0.0), SignBit(-0.0), SignBit(-2.0)]
+            .into_iter()
+            .map(SignCategory)
+            .collect();
+        assert_eq!(cv.indices(0), &set![0]);
+        assert_eq!(cv.indices(1), &set![1, 2]);
+        assert_eq!(cv.count(2), 0);
+        assert_eq!(cv[1].into_inner(), SignBit(-0.0));
+    }
+
+    #[test]
+    fn test_bitmap_backend() {
+        let mut cv: CategoryVec<_, 4, BitmapIndex> = (0..8).map(|i| Potts(i % 4)).collect();
+        cv.set(1, Potts(3));
+        assert_eq!(cv.indices(3).iter().collect::<Vec<_>>(), vec![1, 3, 7]);
+        assert_eq!(cv.swap_remove(0), Potts(0));
+        assert_eq!(cv.indices(3).iter().collect::<Vec<_>>(), vec![0, 1, 3]);
+        assert_eq!(cv.count(0), 1);
+        assert_eq!(cv.values(0).collect::<Vec<_>>(), vec![&Potts(0)]);
+    }
+
+    #[test]
+    fn test_set_moves_index() {
+        let mut cv: CategoryVec<_, 4> = (0..8).map(|i| Potts(i % 4)).collect();
+        cv.set(1, Potts(3));
+        assert_eq!(cv.indices(1), &set![5]);
+        assert_eq!(cv.indices(3), &set![1, 3, 7]);
+        assert_eq!(cv.count(0) + cv.count(1) + cv.count(2) + cv.count(3), 8);
+
+        cv.set(1, Potts(3));
+        assert_eq!(cv.count(3), 3);
+    }
+
+    #[test]
+    fn test_pop_swap_remove_truncate() {
+        let mut cv: CategoryVec<_, 4> = vec![Potts(0), Potts(1), Potts(2), Potts(1)].into();
+        assert_eq!(cv.swap_remove(0), Potts(0));
+        assert_eq!(cv.indices(1), &set![0, 1]);
+        assert_eq!(cv.indices(2), &set![2]);
+        assert_eq!(cv.pop(), Some(Potts(2)));
+        assert_eq!(cv.count(2), 0);
+        cv.truncate(1);
+        assert_eq!(cv.indices(1), &set![0]);
+        cv.clear();
+        assert_eq!(cv.count(1), 0);
+        assert_eq!(cv.pop(), None);
+    }
+
+    #[test]
+    fn test_values_random() {
+        let cv: CategoryVec<_, 4> = vec![Potts(2), Potts(0), Potts(2)].into();
+        let mut values: Vec<_> = cv.values(2).collect();
+        values.dedup();
+        assert_eq!(values, vec![&Potts(2)]);
+        let mut rng = WyRand::new();
+        assert_eq!(cv.random(0, &mut rng), Some(1));
+        assert_eq!(cv.random(3, &mut rng), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Category out of bounds")]
+    fn test_category_out_of_bounds() {
+        let mut cv = CategoryVec::<Potts, 4>::new();
+        cv.push(Potts(4));
+    }
+}
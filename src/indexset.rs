@@ -0,0 +1,1035 @@
+use crate::IndexBackend;
+use fastset::Set;
+use nanorand::{Rng, WyRand};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::any::type_name;
+use std::fmt;
+use std::slice::Iter;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for usize {}
+    impl Sealed for u32 {}
+}
+
+/// An unsigned integer type in which an `IndexSet` stores its indices, implemented for `usize`
+/// and `u32`.
+pub trait IndexType: Copy + Default + Eq + fmt::Debug + private::Sealed {
+    /// Marks a slot whose index is not a member of the set. It is the largest value of the type,
+    /// so only indices below it can be stored.
+    const VACANT: Self;
+
+    /// The number of distinct indices the type can store.
+    const MAX_LEN: usize;
+
+    /// Narrows an index known to be below `VACANT`.
+    fn from_usize(value: usize) -> Self;
+
+    /// Widens a stored value back into a `usize`.
+    fn to_usize(self) -> usize;
+}
+
+impl IndexType for usize {
+    const VACANT: Self = usize::MAX;
+    const MAX_LEN: usize = usize::MAX;
+
+    #[inline(always)]
+    fn from_usize(value: usize) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+impl IndexType for u32 {
+    const VACANT: Self = u32::MAX;
+    const MAX_LEN: usize = u32::MAX as usize;
+
+    #[inline(always)]
+    fn from_usize(value: usize) -> Self {
+        value as u32
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+/// The number of slots in the smallest block of the slot table of an `IndexSet`, as a power of
+/// two.
+const MIN_SHIFT: usize = 6;
+
+/// Returns the number of slots in a block of a slot table of `len` slots, as a power of two: the
+/// smallest power of two at least `√len`, and no fewer than `1 << MIN_SHIFT`.
+#[inline(always)]
+fn shift_for(len: usize) -> usize {
+    let bits = (usize::BITS - len.saturating_sub(1).leading_zeros()) as usize;
+    MIN_SHIFT.max(bits.div_ceil(2))
+}
+
+/// Returns the number of blocks of `1 << shift` slots covering `len` slots.
+#[inline(always)]
+fn blocks(len: usize, shift: usize) -> usize {
+    (len + (1 << shift) - 1) >> shift
+}
+
+/// Maps an index to the position of its slot, given the rotation of each block of
+/// `1 << shift` slots.
+#[inline(always)]
+fn physical(heads: &[usize], shift: usize, index: usize) -> usize {
+    let mask = (1 << shift) - 1;
+    (index & !mask) | ((index + heads[index >> shift]) & mask)
+}
+
+/// Maps the position of a slot back to its index, given the rotation of each block of
+/// `1 << shift` slots.
+#[inline(always)]
+fn logical(heads: &[usize], shift: usize, pos: usize) -> usize {
+    let mask = (1 << shift) - 1;
+    (pos & !mask) | (pos.wrapping_sub(heads[pos >> shift]) & mask)
+}
+
+/// A set of element indices, as kept by `SignVec` for each of its buckets.
+///
+/// Members are stored densely in insertion order next to a slot table mapping each index to its
+/// position in the dense list, so `insert`, `remove`, `contains` and `random` are O(1).
+///
+/// Unlike a general purpose set it can also renumber its members when an element is inserted
+/// into or removed from the middle of the owning vector. The slot table is cut into blocks of
+/// about `√n` slots for a table of `n` slots, each stored as a ring with its own rotation
+/// offset, and the dense list refers to slots rather than to indices. Shifting every index
+/// after a position then rotates each later block by one and moves at most half a block of
+/// slots, so it takes O(√n) steps instead of renumbering every member. The blocks are resized
+/// as the table grows past the next power of four, which costs O(n) and so amortizes to O(1) per
+/// slot.
+///
+/// An `IndexSet` compares equal to a `fastset::Set` holding the same indices, and converting
+/// into a `Set` gives access to its set algebra.
+///
+/// Indices are stored as `usize` by default. An `IndexSet<u32>` halves the memory of both the
+/// member list and the slot table, and holds indices below `u32::MAX`. As with the hasher of a
+/// `HashMap`, `new`, `with_max` and the `From` conversions are provided for the default only;
+/// other index types are built with `default`, `collect` or `IndexBackend::with_max`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::IndexSet;
+///
+/// let mut set = IndexSet::with_max(10);
+/// assert!(set.insert(3));
+/// assert!(!set.insert(3));
+/// assert!(set.contains(&3));
+/// assert!(set.remove(&3));
+/// assert!(set.is_empty());
+///
+/// let mut compact = IndexSet::<u32>::default();
+/// compact.insert(7);
+/// assert!(compact.contains(&7));
+/// ```
+#[derive(Clone, Default)]
+pub struct IndexSet<I: IndexType = usize> {
+    /// The members, as positions in `slots`, in insertion order.
+    dense: Vec<I>,
+    /// The place of each index in `dense`, or `VACANT`, in blocks of `1 << shift` slots.
+    slots: Vec<I>,
+    /// The rotation of each block of `slots`. Only full blocks are ever rotated, so the last
+    /// block can grow and shrink at its end.
+    heads: Vec<usize>,
+    /// The number of slots in a block of `slots`, as a power of two, following `shift_for` as
+    /// the table grows and shrinks.
+    shift: usize,
+}
+
+impl IndexSet {
+    /// Creates a new, empty `IndexSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let set = IndexSet::new();
+    /// assert!(set.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty `IndexSet` with room for indices up to `max_element` before the slot
+    /// table needs to grow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::with_max(100);
+    /// set.insert(100);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    #[inline(always)]
+    pub fn with_max(max_element: usize) -> Self {
+        <Self as IndexBackend>::with_max(max_element)
+    }
+}
+
+impl<I: IndexType> IndexSet<I> {
+    /// Returns the number of indices, counting from zero, the set has room for without
+    /// reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let set = IndexSet::with_max(200);
+    /// assert!(set.capacity() > 200);
+    /// ```
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Reserves room for indices up to `new_max_element`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::new();
+    /// set.reserve(200);
+    /// assert!(set.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn reserve(&mut self, new_max_element: usize) {
+        let wanted = new_max_element.saturating_add(1);
+        self.slots.reserve(wanted.saturating_sub(self.slots.len()));
+    }
+
+    /// Shrinks the set's storage as much as possible while keeping room for indices below
+    /// `min_capacity`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::with_max(1000);
+    /// set.insert(3);
+    /// set.shrink_to(0);
+    /// assert!(set.contains(&3));
+    /// ```
+    #[inline(always)]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let end = self.iter().max().map_or(0, |i| i + 1);
+        if end < self.slots.len() {
+            // The block the table now ends in must not stay rotated.
+            if end & ((1 << self.shift) - 1) != 0 {
+                self.unrotate(end >> self.shift);
+            }
+            self.slots.truncate(end);
+            self.heads.truncate(blocks(end, self.shift));
+            if shift_for(end) < self.shift {
+                self.reblock(shift_for(end));
+            }
+        }
+        self.slots.shrink_to(min_capacity);
+        self.heads.shrink_to(blocks(min_capacity, self.shift));
+        self.dense.shrink_to(min_capacity.min(self.dense.len()));
+    }
+
+    /// Shrinks the set's storage as much as possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::with_max(1000);
+    /// set.insert(3);
+    /// set.shrink_to_fit();
+    /// assert!(set.contains(&3));
+    /// ```
+    #[inline(always)]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Returns the number of indices in the set.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    /// Returns `true` if the set holds no indices.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// Returns an iterator over the indices in the set, in no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let set = IndexSet::from([4, 1]);
+    /// let mut indices: Vec<_> = set.iter().collect();
+    /// indices.sort();
+    /// assert_eq!(indices, vec![1, 4]);
+    /// ```
+    #[inline(always)]
+    pub fn iter(&self) -> IndexSetIter<'_, I> {
+        IndexSetIter {
+            dense: self.dense.iter(),
+            heads: &self.heads,
+            shift: self.shift,
+        }
+    }
+
+    /// Removes every index from the set. This takes time proportional to the number of members,
+    /// not to the largest index.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        // Past a few members per cache line, wiping every slot beats visiting the members.
+        if self.dense.len() > self.slots.len() / 8 {
+            self.slots.fill(I::VACANT);
+        } else {
+            for &value in &self.dense {
+                self.slots[value.to_usize()] = I::VACANT;
+            }
+        }
+        self.dense.clear();
+    }
+
+    /// Adds `value` to the set, returning `false` if it was already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not below `I::VACANT`, that is `usize::MAX` or `u32::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::new();
+    /// assert!(set.insert(5));
+    /// assert!(!set.insert(5));
+    /// ```
+    #[inline(always)]
+    pub fn insert(&mut self, value: usize) -> bool {
+        if value >= self.slots.len() {
+            assert!(
+                value < I::MAX_LEN,
+                "Index {} exceeds the range of IndexSet<{}>",
+                value,
+                type_name::<I>()
+            );
+            self.grow(value + 1);
+        }
+        let pos = physical(&self.heads, self.shift, value);
+        if self.slots[pos] != I::VACANT {
+            return false;
+        }
+        self.slots[pos] = I::from_usize(self.dense.len());
+        self.dense.push(I::from_usize(pos));
+        true
+    }
+
+    /// Adds `base + i` to the set for each set bit `i` of `mask`, growing the storage once for
+    /// the whole word.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index to add is not below `I::VACANT`.
+    #[inline(always)]
+    pub fn insert_mask(&mut self, base: usize, mut mask: u64) {
+        if mask == 0 {
+            return;
+        }
+        let top = base + 63 - mask.leading_zeros() as usize;
+        if top >= self.slots.len() {
+            assert!(
+                top < I::MAX_LEN,
+                "Index {} exceeds the range of IndexSet<{}>",
+                top,
+                type_name::<I>()
+            );
+            self.grow(top + 1);
+        }
+        self.dense.reserve(mask.count_ones() as usize);
+        while mask != 0 {
+            let pos = physical(
+                &self.heads,
+                self.shift,
+                base + mask.trailing_zeros() as usize,
+            );
+            mask &= mask - 1;
+            if self.slots[pos] == I::VACANT {
+                self.slots[pos] = I::from_usize(self.dense.len());
+                self.dense.push(I::from_usize(pos));
+            }
+        }
+    }
+
+    /// Removes `value` from the set, returning `false` if it was not present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    ///
+    /// let mut set = IndexSet::from([5]);
+    /// assert!(set.remove(&5));
+    /// assert!(!set.remove(&5));
+    /// ```
+    #[inline(always)]
+    pub fn remove(&mut self, value: &usize) -> bool {
+        if *value >= self.slots.len() {
+            return false;
+        }
+        let pos = physical(&self.heads, self.shift, *value);
+        let slot = self.slots[pos];
+        if slot == I::VACANT {
+            return false;
+        }
+        self.slots[pos] = I::VACANT;
+        self.dense.swap_remove(slot.to_usize());
+        if let Some(&moved) = self.dense.get(slot.to_usize()) {
+            self.slots[moved.to_usize()] = slot;
+        }
+        true
+    }
+
+    /// Returns `true` if `value` is in the set.
+    #[inline(always)]
+    pub fn contains(&self, value: &usize) -> bool {
+        *value < self.slots.len()
+            && self.slots[physical(&self.heads, self.shift, *value)] != I::VACANT
+    }
+
+    /// Returns a uniformly chosen index from the set, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::IndexSet;
+    /// use nanorand::WyRand;
+    ///
+    /// let set = IndexSet::from([7]);
+    /// let mut rng = WyRand::new();
+    /// assert_eq!(set.random(&mut rng), Some(7));
+    /// ```
+    #[inline(always)]
+    pub fn random(&self, rng: &mut WyRand) -> Option<usize> {
+        match self.dense.is_empty() {
+            true => None,
+            false => {
+                let pos = self.dense[rng.generate_range(0..self.dense.len())];
+                Some(logical(&self.heads, self.shift, pos.to_usize()))
+            }
+        }
+    }
+
+    /// Extends the slot table to `len` vacant slots. The last block is never rotated, so the new
+    /// slots simply follow it.
+    #[inline(always)]
+    fn grow(&mut self, len: usize) {
+        self.slots.resize(len, I::VACANT);
+        self.fit();
+    }
+
+    /// Matches the blocks to the length of the slot table. They are resized once the table
+    /// outgrows them, but only shrunk once it is a sixteenth of the size they suit, so a table
+    /// whose length hovers around a power of four is not cut up again on every shift.
+    #[inline(always)]
+    fn fit(&mut self) {
+        let shift = shift_for(self.slots.len());
+        if shift > self.shift || shift + 1 < self.shift {
+            self.reblock(shift);
+        } else {
+            self.heads.resize(blocks(self.slots.len(), shift), 0);
+        }
+    }
+
+    /// Cuts the slot table into blocks of `1 << shift` slots, undoing every rotation first.
+    #[cold]
+    fn reblock(&mut self, shift: usize) {
+        for block in 0..self.heads.len() {
+            self.unrotate(block);
+        }
+        self.shift = shift;
+        self.heads.clear();
+        self.heads.resize(blocks(self.slots.len(), shift), 0);
+    }
+
+    /// Returns `true` if `block` holds `1 << shift` slots.
+    #[inline(always)]
+    fn is_full(&self, block: usize) -> bool {
+        (block + 1) << self.shift <= self.slots.len()
+    }
+
+    /// Stores `slot` at position `pos` of the slot table and points its member there.
+    #[inline(always)]
+    fn place(&mut self, pos: usize, slot: I) {
+        self.slots[pos] = slot;
+        if slot != I::VACANT {
+            self.dense[slot.to_usize()] = I::from_usize(pos);
+        }
+    }
+
+    /// Points the members of the slots from `start` on back at their positions.
+    #[inline(always)]
+    fn repoint(&mut self, start: usize) {
+        for pos in start..self.slots.len() {
+            let slot = self.slots[pos];
+            if slot != I::VACANT {
+                self.dense[slot.to_usize()] = I::from_usize(pos);
+            }
+        }
+    }
+
+    /// Undoes the rotation of the full block `block`.
+    fn unrotate(&mut self, block: usize) {
+        let head = self.heads[block];
+        if head == 0 {
+            return;
+        }
+        let base = block << self.shift;
+        let end = base + (1 << self.shift);
+        self.slots[base..end].rotate_left(head);
+        self.heads[block] = 0;
+        for pos in base..end {
+            let slot = self.slots[pos];
+            if slot != I::VACANT {
+                self.dense[slot.to_usize()] = I::from_usize(pos);
+            }
+        }
+    }
+
+    /// Opens a vacant slot at `offset` in the full block `block`, returning the slot pushed out
+    /// of its end. Whichever side of `offset` is shorter is moved.
+    fn open_in_block(&mut self, block: usize, offset: usize) -> I {
+        let base = block << self.shift;
+        let mask = (1 << self.shift) - 1;
+        let at = |head: usize, i: usize| base | ((head + i) & mask);
+        let mut head = self.heads[block];
+        let out = self.slots[at(head, mask)];
+        if offset <= mask / 2 {
+            // Rotating back by one brings the last slot to the front, where the slots before
+            // `offset` move down over it.
+            head = (head + mask) & mask;
+            self.heads[block] = head;
+            for i in 0..offset {
+                self.place(at(head, i), self.slots[at(head, i + 1)]);
+            }
+        } else {
+            for i in (offset..mask).rev() {
+                self.place(at(head, i + 1), self.slots[at(head, i)]);
+            }
+        }
+        self.slots[at(head, offset)] = I::VACANT;
+        out
+    }
+
+    /// Closes the slot at `offset` in the full block `block`, returning the position of the slot
+    /// freed at its end. Whichever side of `offset` is shorter is moved.
+    fn close_in_block(&mut self, block: usize, offset: usize) -> usize {
+        let base = block << self.shift;
+        let mask = (1 << self.shift) - 1;
+        let at = |head: usize, i: usize| base | ((head + i) & mask);
+        let head = self.heads[block];
+        if offset <= mask / 2 {
+            // The slots before `offset` move up, and rotating forward by one turns the first
+            // slot into the last.
+            for i in (0..offset).rev() {
+                self.place(at(head, i + 1), self.slots[at(head, i)]);
+            }
+            self.heads[block] = (head + 1) & mask;
+            at(head, 0)
+        } else {
+            for i in offset..mask {
+                self.place(at(head, i), self.slots[at(head, i + 1)]);
+            }
+            at(head, mask)
+        }
+    }
+}
+
+impl<I: IndexType> IndexBackend for IndexSet<I> {
+    type Iter<'a>
+        = IndexSetIter<'a, I>
+    where
+        I: 'a;
+
+    const MAX_LEN: usize = I::MAX_LEN;
+
+    #[inline(always)]
+    fn with_max(max_element: usize) -> Self {
+        Self {
+            dense: Vec::new(),
+            slots: Vec::with_capacity(max_element.saturating_add(1)),
+            heads: Vec::new(),
+            shift: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        IndexSet::len(self)
+    }
+
+    #[inline(always)]
+    fn contains(&self, value: &usize) -> bool {
+        IndexSet::contains(self, value)
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, value: usize) -> bool {
+        IndexSet::insert(self, value)
+    }
+
+    #[inline(always)]
+    fn insert_mask(&mut self, base: usize, mask: u64) {
+        IndexSet::insert_mask(self, base, mask)
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, value: &usize) -> bool {
+        IndexSet::remove(self, value)
+    }
+
+    #[inline(always)]
+    fn clear(&mut self) {
+        IndexSet::clear(self)
+    }
+
+    #[inline(always)]
+    fn iter(&self) -> Self::Iter<'_> {
+        IndexSet::iter(self)
+    }
+
+    #[inline(always)]
+    fn random(&self, rng: &mut WyRand) -> Option<usize> {
+        IndexSet::random(self, rng)
+    }
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        IndexSet::capacity(self)
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, new_max_element: usize) {
+        IndexSet::reserve(self, new_max_element)
+    }
+
+    #[inline(always)]
+    fn shrink_to(&mut self, min_capacity: usize) {
+        IndexSet::shrink_to(self, min_capacity)
+    }
+
+    /// Each full block after `at` is rotated by one, and within the block of `at` at most half a
+    /// block of slots moves.
+    fn insert_shift(&mut self, at: usize) {
+        if at >= self.slots.len() {
+            // No member is at or above `at`.
+            return;
+        }
+        let (shift, mask) = (self.shift, (1 << self.shift) - 1);
+        let mut block = at >> shift;
+        let mut carry = I::VACANT;
+        let mut tail = at;
+        if self.is_full(block) {
+            // The slot pushed out of each full block becomes the first of the next one.
+            carry = self.open_in_block(block, at & mask);
+            block += 1;
+            while self.is_full(block) {
+                let head = (self.heads[block] + mask) & mask;
+                self.heads[block] = head;
+                let pos = (block << shift) | head;
+                let out = self.slots[pos];
+                self.place(pos, carry);
+                carry = out;
+                block += 1;
+            }
+            tail = block << shift;
+        }
+        self.slots.insert(tail, carry);
+        self.repoint(tail);
+        self.fit();
+    }
+
+    fn remove_shift(&mut self, at: usize) -> bool {
+        if at >= self.slots.len() {
+            return false;
+        }
+        let removed = self.remove(&at);
+        let (shift, mask) = (self.shift, (1 << self.shift) - 1);
+        let mut block = at >> shift;
+        let mut tail = at;
+        if self.is_full(block) {
+            // Each full block takes the first slot of the next one into the slot freed at its end.
+            let mut free = self.close_in_block(block, at & mask);
+            block += 1;
+            while self.is_full(block) {
+                let head = self.heads[block];
+                let pos = (block << shift) | head;
+                self.place(free, self.slots[pos]);
+                self.heads[block] = (head + 1) & mask;
+                free = pos;
+                block += 1;
+            }
+            tail = block << shift;
+            if tail == self.slots.len() {
+                // The last block was full; it becomes the unrotated tail, one slot short.
+                self.slots[free] = I::VACANT;
+                self.unrotate(block - 1);
+                self.slots.pop();
+                self.fit();
+                return removed;
+            }
+            self.place(free, self.slots[tail]);
+        }
+        self.slots.remove(tail);
+        self.repoint(tail);
+        self.fit();
+        removed
+    }
+
+    fn remove_range_shift(&mut self, start: usize, end: usize) {
+        let end = end.min(self.slots.len());
+        if start >= end {
+            return;
+        }
+        let width = end - start;
+        if width.saturating_mul(1 << self.shift) < self.slots.len() {
+            for at in (start..end).rev() {
+                self.remove_shift(at);
+            }
+            return;
+        }
+        // Wide ranges rebuild the slot table, unrotated.
+        let (heads, shift) = (&self.heads, self.shift);
+        self.dense.retain_mut(|value| {
+            let index = logical(heads, shift, value.to_usize());
+            if index < start {
+                *value = I::from_usize(index);
+                true
+            } else if index < end {
+                false
+            } else {
+                *value = I::from_usize(index - width);
+                true
+            }
+        });
+        let len = self.slots.len() - width;
+        self.slots.truncate(len);
+        self.slots.fill(I::VACANT);
+        self.heads.clear();
+        self.fit();
+        for (pos, &value) in self.dense.iter().enumerate() {
+            self.slots[value.to_usize()] = I::from_usize(pos);
+        }
+    }
+}
+
+impl<I: IndexType> fmt::Debug for IndexSet<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Two sets are equal if they hold the same indices, irrespective of their order.
+impl<I: IndexType> PartialEq for IndexSet<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(&value))
+    }
+}
+
+impl<I: IndexType> Eq for IndexSet<I> {}
+
+impl<I: IndexType> PartialEq<Set> for IndexSet<I> {
+    fn eq(&self, other: &Set) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(&value))
+    }
+}
+
+impl<I: IndexType> PartialEq<IndexSet<I>> for Set {
+    fn eq(&self, other: &IndexSet<I>) -> bool {
+        other == self
+    }
+}
+
+impl<I: IndexType> From<&IndexSet<I>> for Set {
+    fn from(set: &IndexSet<I>) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl From<Vec<usize>> for IndexSet {
+    fn from(values: Vec<usize>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl From<&[usize]> for IndexSet {
+    fn from(values: &[usize]) -> Self {
+        values.iter().copied().collect()
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for IndexSet {
+    fn from(values: [usize; N]) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl<I: IndexType> From<IndexSet<I>> for Vec<usize> {
+    fn from(set: IndexSet<I>) -> Self {
+        set.iter().collect()
+    }
+}
+
+/// A set is serialized as the sequence of its indices, in no particular order.
+impl<I: IndexType> Serialize for IndexSet<I> {
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, I: IndexType> Deserialize<'de> for IndexSet<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<usize>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+impl<I: IndexType> Extend<usize> for IndexSet<I> {
+    fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, I: IndexType> Extend<&'a usize> for IndexSet<I> {
+    fn extend<T: IntoIterator<Item = &'a usize>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<I: IndexType> FromIterator<usize> for IndexSet<I> {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, I: IndexType> FromIterator<&'a usize> for IndexSet<I> {
+    fn from_iter<T: IntoIterator<Item = &'a usize>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl<'a, I: IndexType> IntoIterator for &'a IndexSet<I> {
+    type Item = usize;
+    type IntoIter = IndexSetIter<'a, I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the indices of an `IndexSet`, in no particular order.
+#[derive(Clone, Debug)]
+pub struct IndexSetIter<'a, I: IndexType> {
+    dense: Iter<'a, I>,
+    heads: &'a [usize],
+    shift: usize,
+}
+
+impl<I: IndexType> Iterator for IndexSetIter<'_, I> {
+    type Item = usize;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<usize> {
+        let pos = self.dense.next()?;
+        Some(logical(self.heads, self.shift, pos.to_usize()))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.dense.size_hint()
+    }
+}
+
+impl<I: IndexType> DoubleEndedIterator for IndexSetIter<'_, I> {
+    #[inline(always)]
+    fn next_back(&mut self) -> Option<usize> {
+        let pos = self.dense.next_back()?;
+        Some(logical(self.heads, self.shift, pos.to_usize()))
+    }
+}
+
+impl<I: IndexType> ExactSizeIterator for IndexSetIter<'_, I> {}
+
+impl<I: IndexType> std::iter::FusedIterator for IndexSetIter<'_, I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastset::set;
+
+    fn sorted(set: &IndexSet) -> Vec<usize> {
+        let mut values: Vec<_> = set.iter().collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = IndexSet::with_max(4);
+        assert!(set.insert(9));
+        assert!(set.insert(2));
+        assert!(set.insert(5));
+        assert!(set.remove(&2));
+        assert!(!set.remove(&2));
+        assert!(!set.remove(&100));
+        assert!(set.contains(&5) && set.contains(&9));
+        assert_eq!(sorted(&set), vec![5, 9]);
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.contains(&9));
+    }
+
+    #[test]
+    fn test_insert_mask() {
+        let mut set = IndexSet::from([130]);
+        set.insert_mask(100, (1 << 30) | (1 << 63) | 0b101);
+        assert_eq!(sorted(&set), vec![100, 102, 130, 163]);
+        assert_eq!(set.len(), 4);
+        set.insert_mask(500, 0);
+        assert!(!set.contains(&500));
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut set = IndexSet::from([0, 3, 4, 7]);
+        set.insert_shift(3);
+        assert_eq!(sorted(&set), vec![0, 4, 5, 8]);
+        assert!(!set.contains(&3) && set.contains(&8));
+        set.insert_shift(20);
+        assert_eq!(sorted(&set), vec![0, 4, 5, 8]);
+
+        assert!(set.remove_shift(4));
+        assert_eq!(sorted(&set), vec![0, 4, 7]);
+        assert!(!set.remove_shift(1));
+        assert_eq!(sorted(&set), vec![0, 3, 6]);
+        assert!(set.contains(&6) && !set.contains(&7));
+        assert!(!set.remove_shift(20));
+
+        let mut set = IndexSet::from([9, 1, 4, 5, 7]);
+        set.remove_range_shift(3, 6);
+        assert_eq!(sorted(&set), vec![1, 4, 6]);
+        assert!(set.remove(&6) && set.remove(&1));
+        assert_eq!(sorted(&set), vec![4]);
+    }
+
+    #[test]
+    fn test_shift_across_blocks() {
+        // A model vector of flags, shifted along with the set across many rotated blocks and past
+        // a power of four, where the blocks are resized.
+        let len = 4096 - 20;
+        let block = 1 << shift_for(len);
+        let mut rng = WyRand::new_seed(7);
+        let mut model: Vec<bool> = (0..len).map(|_| rng.generate_range(0..3u8) == 0).collect();
+        let mut set: IndexSet = (0..len).filter(|&i| model[i]).collect();
+        set.grow(len);
+        for round in 0..2000 {
+            let at = match round % 4 {
+                0 => rng.generate_range(0..8),
+                1 => rng.generate_range(0..model.len()),
+                2 => model.len() - 1 - rng.generate_range(0..8),
+                _ => rng.generate_range(0..model.len()) & !(block - 1),
+            };
+            if model.len() < len || (model.len() < len + 10 * block && rng.generate::<bool>()) {
+                set.insert_shift(at);
+                model.insert(at, false);
+                if round % 3 == 0 {
+                    set.insert(at);
+                    model[at] = true;
+                }
+            } else {
+                assert_eq!(set.remove_shift(at), model.remove(at));
+            }
+            if round % 97 == 0 {
+                let start = rng.generate_range(0..model.len());
+                let end = (start + rng.generate_range(0..3 * block)).min(model.len());
+                set.remove_range_shift(start, end);
+                model.drain(start..end);
+                for _ in start..end {
+                    set.insert_shift(start);
+                    model.insert(start, false);
+                }
+            }
+        }
+        let expected: Vec<usize> = (0..model.len()).filter(|&i| model[i]).collect();
+        assert_eq!(sorted(&set), expected);
+        assert!((0..model.len()).all(|i| set.contains(&i) == model[i]));
+        assert!(set.shift > shift_for(len));
+
+        // Shrinking the table cuts it back into smaller blocks.
+        let keep = expected.iter().position(|&i| i >= 100).unwrap();
+        for &i in &expected[keep..] {
+            assert!(set.remove(&i));
+        }
+        set.shrink_to(0);
+        assert_eq!(set.shift, MIN_SHIFT);
+        assert_eq!(sorted(&set), expected[..keep]);
+        let expected = &expected[..keep];
+        for &i in expected {
+            assert!(set.remove(&i));
+        }
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_u32_indices() {
+        let mut set: IndexSet<u32> = [0, 3, 4, 7].into_iter().collect();
+        set.insert_shift(3);
+        assert!(set.remove_shift(0));
+        assert_eq!(set, set![3, 4, 7]);
+        assert_eq!(Vec::from(set.clone()).len(), 3);
+        assert_eq!(IndexBackend::iter(&set).max(), Some(7));
+
+        assert_eq!(<IndexSet<u32> as IndexBackend>::MAX_LEN, u32::MAX as usize);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the range of IndexSet<u32>")]
+    fn test_u32_overflow() {
+        IndexSet::<u32>::default().insert(u32::MAX as usize);
+    }
+
+    #[test]
+    fn test_fastset_interop() {
+        let set = IndexSet::from(vec![1, 2, 3]);
+        assert_eq!(set, set![3, 2, 1]);
+        assert_eq!(set![1, 2, 3], set);
+        assert_eq!(Set::from(&set).union(&set![4]), set![1, 2, 3, 4]);
+        assert_eq!(set.iter().max(), Some(3));
+    }
+}
@@ -0,0 +1,258 @@
+//! `Signable` implementations for third-party numeric types, each behind the cargo feature
+//! named after its crate.
+
+#[cfg(feature = "rust_decimal")]
+mod decimal {
+    use crate::{Sign, Signable};
+    use rust_decimal::Decimal;
+
+    impl Signable for Decimal {
+        #[inline(always)]
+        fn sign(&self) -> Sign {
+            if self.is_zero() {
+                Sign::Zero
+            } else if self.is_sign_negative() {
+                Sign::Minus
+            } else {
+                Sign::Plus
+            }
+        }
+    }
+
+    impl From<Decimal> for Sign {
+        fn from(num: Decimal) -> Self {
+            num.sign()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{svec, SignVec};
+        use fastset::{set, Set};
+
+        #[test]
+        fn test_decimal() {
+            let vec = svec![Decimal::new(-125, 2), Decimal::ZERO, Decimal::new(3, 0)];
+            assert_eq!(vec.indices(Sign::Minus), &set![0]);
+            assert_eq!(vec.indices(Sign::Zero), &set![1]);
+            assert_eq!(vec.indices(Sign::Plus), &set![2]);
+            assert_eq!(Sign::from(-Decimal::ZERO), Sign::Zero);
+        }
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+mod bigint {
+    use crate::{Sign, Signable};
+    use num_bigint::BigInt;
+
+    impl Signable for BigInt {
+        #[inline(always)]
+        fn sign(&self) -> Sign {
+            match BigInt::sign(self) {
+                num_bigint::Sign::Plus => Sign::Plus,
+                num_bigint::Sign::Minus => Sign::Minus,
+                num_bigint::Sign::NoSign => Sign::Zero,
+            }
+        }
+    }
+
+    impl From<BigInt> for Sign {
+        fn from(num: BigInt) -> Self {
+            Signable::sign(&num)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{svec, SignVec};
+        use fastset::{set, Set};
+
+        #[test]
+        fn test_bigint() {
+            let mut vec = svec![BigInt::from(-7), BigInt::from(0), BigInt::from(1) << 100u32];
+            assert_eq!(vec.indices(Sign::Minus), &set![0]);
+            assert_eq!(vec.indices(Sign::Zero), &set![1]);
+            assert_eq!(vec.indices(Sign::Plus), &set![2]);
+            vec.set(2, BigInt::from(-1) << 100u32);
+            assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+            assert_eq!(Sign::from(BigInt::from(3)), Sign::Plus);
+        }
+    }
+}
+
+#[cfg(feature = "num-rational")]
+mod rational {
+    use crate::{Sign, Signable};
+    use num_rational::Ratio;
+
+    /// The sign of `numer / denom`, which also holds for ratios built with `Ratio::new_raw` and
+    /// a negative denominator.
+    impl<T> Signable for Ratio<T>
+    where
+        T: Signable,
+    {
+        #[inline(always)]
+        fn sign(&self) -> Sign {
+            match (self.numer().sign(), self.denom().sign()) {
+                (Sign::Zero, _) => Sign::Zero,
+                (numer, denom) if numer == denom => Sign::Plus,
+                _ => Sign::Minus,
+            }
+        }
+    }
+
+    impl<T> From<Ratio<T>> for Sign
+    where
+        T: Signable,
+    {
+        fn from(num: Ratio<T>) -> Self {
+            num.sign()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{svec, SignVec};
+        use fastset::{set, Set};
+
+        #[test]
+        fn test_ratio() {
+            let vec = svec![
+                Ratio::new(-1, 3),
+                Ratio::new(0, 5),
+                Ratio::new(2, 7),
+                Ratio::new_raw(1i64, -2)
+            ];
+            assert_eq!(vec.indices(Sign::Minus), &set![0, 3]);
+            assert_eq!(vec.indices(Sign::Zero), &set![1]);
+            assert_eq!(vec.indices(Sign::Plus), &set![2]);
+        }
+    }
+}
+
+#[cfg(feature = "half")]
+mod half_floats {
+    use crate::{Sign, Signable};
+    use half::{bf16, f16};
+
+    macro_rules! signable_half {
+        ($($t:ty),*) => {$(
+            impl Signable for $t {
+                #[inline(always)]
+                fn sign(&self) -> Sign {
+                    // Widening is exact, NaN sign bits included.
+                    self.to_f32().sign()
+                }
+
+                #[inline(always)]
+                fn is_nan(&self) -> bool {
+                    <$t>::is_nan(*self)
+                }
+            }
+
+            impl From<$t> for Sign {
+                fn from(num: $t) -> Self {
+                    num.sign()
+                }
+            }
+        )*};
+    }
+
+    signable_half!(f16, bf16);
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{svec, SignVec};
+        use fastset::{set, Set};
+
+        #[test]
+        fn test_half() {
+            let vec = svec![f16::from_f32(-0.5), f16::ZERO, f16::NAN, f16::MAX];
+            assert_eq!(vec.indices(Sign::Minus), &set![0]);
+            assert_eq!(vec.indices(Sign::Zero), &set![1]);
+            assert_eq!(vec.indices_nan(), &set![2]);
+            assert_eq!(vec.indices(Sign::Plus), &set![3]);
+
+            let vec = svec![bf16::from_f32(2.0), bf16::NEG_ONE];
+            assert_eq!(vec.indices(Sign::Plus), &set![0]);
+            assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        }
+    }
+}
+
+#[cfg(feature = "ordered-float")]
+mod ordered {
+    use crate::{Sign, Signable};
+    use ordered_float::{FloatCore, NotNan, OrderedFloat};
+
+    impl<T> Signable for OrderedFloat<T>
+    where
+        T: Signable,
+    {
+        #[inline(always)]
+        fn sign(&self) -> Sign {
+            self.0.sign()
+        }
+
+        #[inline(always)]
+        fn is_nan(&self) -> bool {
+            self.0.is_nan()
+        }
+    }
+
+    impl<T> Signable for NotNan<T>
+    where
+        T: Signable + FloatCore,
+    {
+        #[inline(always)]
+        fn sign(&self) -> Sign {
+            (**self).sign()
+        }
+    }
+
+    impl<T> From<OrderedFloat<T>> for Sign
+    where
+        T: Signable,
+    {
+        fn from(num: OrderedFloat<T>) -> Self {
+            num.sign()
+        }
+    }
+
+    impl<T> From<NotNan<T>> for Sign
+    where
+        T: Signable + FloatCore,
+    {
+        fn from(num: NotNan<T>) -> Self {
+            num.sign()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{svec, SignVec};
+        use fastset::{set, Set};
+
+        #[test]
+        fn test_ordered_float() {
+            let vec = svec![
+                OrderedFloat(-1.0),
+                OrderedFloat(f64::NAN),
+                OrderedFloat(0.0)
+            ];
+            assert_eq!(vec.indices(Sign::Minus), &set![0]);
+            assert_eq!(vec.indices_nan(), &set![1]);
+            assert_eq!(vec.indices(Sign::Zero), &set![2]);
+
+            let vec = svec![NotNan::new(2.5f32).unwrap(), NotNan::new(-2.5f32).unwrap()];
+            assert_eq!(vec.indices(Sign::Plus), &set![0]);
+            assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        }
+    }
+}
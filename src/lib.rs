@@ -6,14 +6,19 @@
 //! ![GitHub Workflow Status](https://github.com/b-vitamins/signvec/actions/workflows/rust.yml/badge.svg)
 //!
 //! `SignVec` extends the capabilities of the traditional `Vec` by providing additional functionalities
-//! to efficiently track and manipulate elements based on their sign (positive or negative) using the `Signable` trait.
+//! to efficiently track and manipulate elements based on their sign (positive, negative or zero) using the `Signable` trait.
 //!
 //! ## Features
 //!
 //! - **Sign-Aware Element Tracking**: Tracks the sign of elements for optimized sign-specific operations.
-//! - **Efficient Updates**: Maintains high performance even with frequent modifications.
+//! - **Signed Zeros**: The `SignBit` wrapper classifies floats by their sign bit, so `-0.0` counts as negative.
+//! - **Explicit NaN Handling**: A `NanPolicy` decides whether NaNs are rejected, tracked on their own or classified by sign bit.
+//! - **Efficient Updates**: Maintains high performance even with frequent modifications; positional inserts and removes renumber the `IndexSet` of each bucket in place.
 //! - **Versatile Operations**: Provides methods for element counting, access, and manipulation based on sign.
+//! - **Pluggable Storage**: The index sets are any `IndexBackend`; `BitmapIndex` trades the default `IndexSet` for one bit per element with rank and select.
 //! - **Flexible Interface**: Offers both safe and unsafe methods to accommodate various requirements.
+//! - **N-Way Categories**: `CategoryVec` generalizes the sign buckets to any number of categories via the `Categorize` trait.
+//! - **Movable Thresholds**: `ThresholdSignVec` classifies against a pivot and reclassifies only the elements it passes over when moved.
 //! - **Custom Type Support**: Seamlessly integrates with user-defined types via the `Signable` trait.
 //!
 //! ## Usage: Basic operations
@@ -35,11 +40,11 @@
 //!     // Get indices of positive and negative elements
 //!     assert_eq!(
 //!         vector.indices(Sign::Plus).iter().collect::<Vec<_>>(),
-//!         vec![&0, &2]
+//!         vec![0, 2]
 //!     );
 //!     assert_eq!(
 //!         vector.indices(Sign::Minus).iter().collect::<Vec<_>>(),
-//!         vec![&1, &3]
+//!         vec![1, 3]
 //!     );
 //!
 //!     // Retrieve values based on their sign
@@ -53,6 +58,11 @@
 //!     vector.set(1, 5);
 //!     assert_eq!(vector[1], 5);
 //!
+//!     // Zeros are tracked in their own bucket
+//!     vector.set(3, 0);
+//!     assert_eq!(vector.count(Sign::Zero), 1);
+//!     assert_eq!(vector.count(Sign::Minus), 0);
+//!
 //!     // Randomly select an element based on its sign
 //!     let mut rng = WyRand::new();
 //!     if let Some(random_positive) = vector.random(Sign::Plus, &mut rng) {
@@ -109,7 +119,7 @@
 //!
 //!     // Apply market conditions to adjust portfolio balances
 //!     for (index, &factor) in market_conditions.iter().enumerate() {
-//!         portfolio.set(index, portfolio[index] * factor);
+//!         portfolio.modify(index, |x| *x *= factor);
 //!     }
 //!
 //!     // Decision making based on portfolio's sign-aware characteristics
@@ -169,26 +179,317 @@
 //! - Operating System: Guix System
 //! - OS Type: 64-bit
 
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::fmt;
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, Saturating, Wrapping,
+};
+use std::ops::{Mul, MulAssign, Neg, Not};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod backend;
+mod bitmap;
+mod categoryvec;
+mod indexset;
+mod integrations;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod signvec;
-pub use signvec::SignVec;
+mod threshold;
+pub use backend::IndexBackend;
+pub use bitmap::{BitmapIndex, BitmapIter};
+pub use categoryvec::{Categorize, CategoryVec, SignCategory};
+pub use indexset::{IndexSet, IndexSetIter, IndexType};
+pub use signvec::{
+    NanPolicy, SignVec, SignVec32, SignVecBulk, SignVecError, SignVecIterMut, SignVecMut,
+};
+pub use threshold::ThresholdSignVec;
+
+/// Derives `Signable` for structs delegating to a field and for enums with a sign per variant.
+///
+/// Available with the `derive` feature. See the `signvec-derive` crate for the attributes.
+#[cfg(feature = "derive")]
+pub use signvec_derive::Signable;
 
 /// Enum representing the sign of a number.
-#[derive(Debug, PartialEq, Copy, Clone)]
+///
+/// Zero is a sign of its own: `0` and `0.0` classify as `Sign::Zero` rather
+/// than being lumped in with the positive values.
+///
+/// Signs follow the usual algebra: `-` and `!` flip them, `*` multiplies them, and they are
+/// ordered `Minus < Zero < Plus`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::Sign;
+///
+/// assert_eq!(Sign::Minus * Sign::Minus, Sign::Plus);
+/// assert_eq!(-Sign::Plus, Sign::Minus);
+/// assert_eq!(Sign::of(&-3.5), Sign::Minus);
+/// assert_eq!(Sign::Minus.apply(2), -2);
+/// assert_eq!(i8::from(Sign::Plus), 1);
+/// assert_eq!("-".parse::<Sign>(), Ok(Sign::Minus));
+/// assert!(Sign::Minus < Sign::Zero);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Sign {
     Plus,
     Minus,
+    #[default]
+    Zero,
 }
 
 /// Trait for types that can be classified by a sign.
 pub trait Signable {
     fn sign(&self) -> Sign;
+
+    /// Returns `true` if the value is not a number.
+    ///
+    /// `SignVec` consults this before `sign` and handles such values according to its
+    /// `NanPolicy`. Types reporting NaNs should classify them by their sign bit in `sign`,
+    /// which is what `NanPolicy::SignBit` relies on. The default implementation returns `false`.
+    #[inline(always)]
+    fn is_nan(&self) -> bool {
+        false
+    }
+
+    /// Classifies up to 64 values at once, bit `i` of each mask standing for `vals[i]`.
+    ///
+    /// `SignVec` calls this from `sync` and `extend_from_slice` when it classifies with
+    /// `Signable::sign`. The default implementation calls `is_nan` and `sign` per value; the
+    /// primitive integer and float impls override it with a branch-free loop that the compiler
+    /// turns into SIMD code where the target supports it.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `vals` holds more than 64 values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignMasks, Signable};
+    ///
+    /// let masks = f64::sign_masks(&[1.5, -0.0, f64::NAN, -2.0]);
+    ///
+    /// assert_eq!(masks, SignMasks { plus: 0b0001, minus: 0b1000, nan: 0b0100 });
+    /// assert_eq!(masks.zero(4), 0b0010);
+    /// ```
+    fn sign_masks(vals: &[Self]) -> SignMasks
+    where
+        Self: Sized,
+    {
+        let mut masks = SignMasks::default();
+        for (i, val) in vals.iter().enumerate() {
+            let bit = 1 << i;
+            if val.is_nan() {
+                masks.nan |= bit;
+            } else {
+                match val.sign() {
+                    Sign::Plus => masks.plus |= bit,
+                    Sign::Minus => masks.minus |= bit,
+                    Sign::Zero => {}
+                }
+            }
+        }
+        masks
+    }
+}
+
+/// The signs of up to 64 values as bit masks, as returned by `Signable::sign_masks`.
+///
+/// NaNs are only set in `nan`, and values in none of the masks are zero.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct SignMasks {
+    /// The positive values.
+    pub plus: u64,
+    /// The negative values.
+    pub minus: u64,
+    /// The values reporting `Signable::is_nan`.
+    pub nan: u64,
+}
+
+impl SignMasks {
+    /// Returns the mask of the zeros among the first `len` values.
+    #[inline(always)]
+    pub fn zero(&self, len: usize) -> u64 {
+        let all = if len >= 64 { u64::MAX } else { (1 << len) - 1 };
+        all & !(self.plus | self.minus | self.nan)
+    }
 }
 
 impl Sign {
+    /// Applies the sign to `x`: `Plus` keeps it, `Minus` negates it and `Zero` yields the
+    /// default value, zero for numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::Sign;
+    ///
+    /// assert_eq!(Sign::Minus.apply(4.0), -4.0);
+    /// assert_eq!(Sign::Zero.apply(4), 0);
+    /// ```
+    #[inline(always)]
+    pub fn apply<T>(self, x: T) -> T
+    where
+        T: Neg<Output = T> + Default,
+    {
+        match self {
+            Sign::Plus => x,
+            Sign::Minus => -x,
+            Sign::Zero => T::default(),
+        }
+    }
+
+    /// Returns the opposite sign. `Sign::Zero` is its own opposite.
     pub fn flip(&self) -> Self {
         match self {
             Sign::Plus => Sign::Minus,
             Sign::Minus => Sign::Plus,
+            Sign::Zero => Sign::Zero,
+        }
+    }
+
+    /// Returns the sign of `x`, as classified by `Signable::sign`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::Sign;
+    ///
+    /// assert_eq!(Sign::of(&-7), Sign::Minus);
+    /// assert_eq!(Sign::of(&0.0), Sign::Zero);
+    /// ```
+    #[inline(always)]
+    pub fn of<T>(x: &T) -> Self
+    where
+        T: Signable + ?Sized,
+    {
+        x.sign()
+    }
+}
+
+impl Neg for Sign {
+    type Output = Sign;
+
+    #[inline(always)]
+    fn neg(self) -> Sign {
+        self.flip()
+    }
+}
+
+impl Not for Sign {
+    type Output = Sign;
+
+    #[inline(always)]
+    fn not(self) -> Sign {
+        self.flip()
+    }
+}
+
+/// Multiplies signs: equal signs give `Plus`, opposite signs give `Minus` and `Zero` absorbs.
+impl Mul for Sign {
+    type Output = Sign;
+
+    #[inline(always)]
+    fn mul(self, rhs: Sign) -> Sign {
+        match (self, rhs) {
+            (Sign::Zero, _) | (_, Sign::Zero) => Sign::Zero,
+            (lhs, rhs) if lhs == rhs => Sign::Plus,
+            _ => Sign::Minus,
+        }
+    }
+}
+
+impl MulAssign for Sign {
+    #[inline(always)]
+    fn mul_assign(&mut self, rhs: Sign) {
+        *self = *self * rhs;
+    }
+}
+
+impl PartialOrd for Sign {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders signs as the numbers they stand for: `Minus < Zero < Plus`.
+impl Ord for Sign {
+    #[inline(always)]
+    fn cmp(&self, other: &Self) -> Ordering {
+        i8::from(*self).cmp(&i8::from(*other))
+    }
+}
+
+/// `true` is `Sign::Plus` and `false` is `Sign::Minus`.
+impl From<bool> for Sign {
+    #[inline(always)]
+    fn from(positive: bool) -> Self {
+        if positive {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        }
+    }
+}
+
+/// `Plus` is `1`, `Minus` is `-1` and `Zero` is `0`.
+impl From<Sign> for i8 {
+    #[inline(always)]
+    fn from(sign: Sign) -> Self {
+        match sign {
+            Sign::Plus => 1,
+            Sign::Minus => -1,
+            Sign::Zero => 0,
+        }
+    }
+}
+
+/// Formats `Plus` as `+`, `Minus` as `-` and `Zero` as `0`.
+impl fmt::Display for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Sign::Plus => "+",
+            Sign::Minus => "-",
+            Sign::Zero => "0",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// Error returned when parsing a `Sign` from a string other than `+`, `-` or `0`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseSignError(String);
+
+impl fmt::Display for ParseSignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid sign {:?}, expected \"+\", \"-\" or \"0\"",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseSignError {}
+
+/// Parses the symbols written by `Display`: `+`, `-` and `0`.
+impl FromStr for Sign {
+    type Err = ParseSignError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Sign::Plus),
+            "-" => Ok(Sign::Minus),
+            "0" => Ok(Sign::Zero),
+            _ => Err(ParseSignError(s.to_string())),
         }
     }
 }
@@ -197,7 +498,13 @@ macro_rules! signfrom {
     ($($t:ty),*) => {$(
         impl From<$t> for Sign {
             fn from(num: $t) -> Self {
-                if num >= 0 as $t { Sign::Plus } else { Sign::Minus }
+                if num > 0 as $t {
+                    Sign::Plus
+                } else if num < 0 as $t {
+                    Sign::Minus
+                } else {
+                    Sign::Zero
+                }
             }
         }
     )*};
@@ -207,7 +514,23 @@ macro_rules! signable {
     ($($t:ty),*) => {$(
         impl Signable for $t {
             fn sign(&self) -> Sign {
-                if *self >= 0 as $t { Sign::Plus } else { Sign::Minus }
+                if *self > 0 as $t {
+                    Sign::Plus
+                } else if *self < 0 as $t {
+                    Sign::Minus
+                } else {
+                    Sign::Zero
+                }
+            }
+
+            #[inline(always)]
+            fn sign_masks(vals: &[Self]) -> SignMasks {
+                let (mut plus, mut minus) = (0u64, 0u64);
+                for (i, &val) in vals[..vals.len().min(64)].iter().enumerate() {
+                    plus |= ((val > 0 as $t) as u64) << i;
+                    minus |= ((val < 0 as $t) as u64) << i;
+                }
+                SignMasks { plus, minus, nan: 0 }
             }
         }
     )*};
@@ -221,5 +544,309 @@ macro_rules! svec {
     }};
 }
 
-signfrom!(i8, i16, i32, i64, i128, isize, f32, f64);
-signable!(i8, i16, i32, i64, i128, isize, f32, f64);
+macro_rules! signfrom_float {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Sign {
+            fn from(num: $t) -> Self {
+                num.sign()
+            }
+        }
+    )*};
+}
+
+macro_rules! signable_float {
+    ($($t:ty),*) => {$(
+        impl Signable for $t {
+            fn sign(&self) -> Sign {
+                if *self > 0.0 {
+                    Sign::Plus
+                } else if *self < 0.0 {
+                    Sign::Minus
+                } else if self.is_nan() {
+                    // NaNs carry no magnitude, only a sign bit.
+                    if self.is_sign_negative() { Sign::Minus } else { Sign::Plus }
+                } else {
+                    Sign::Zero
+                }
+            }
+
+            #[inline(always)]
+            fn is_nan(&self) -> bool {
+                <$t>::is_nan(*self)
+            }
+
+            #[inline(always)]
+            fn sign_masks(vals: &[Self]) -> SignMasks {
+                let (mut plus, mut minus, mut nan) = (0u64, 0u64, 0u64);
+                for (i, &val) in vals[..vals.len().min(64)].iter().enumerate() {
+                    // Comparisons with NaN are false, so NaNs only land in `nan`.
+                    plus |= ((val > 0.0) as u64) << i;
+                    minus |= ((val < 0.0) as u64) << i;
+                    nan |= (<$t>::is_nan(val) as u64) << i;
+                }
+                SignMasks { plus, minus, nan }
+            }
+        }
+    )*};
+}
+
+/// Wrapper classifying a float by its IEEE sign bit alone.
+///
+/// The plain `f32`/`f64` impls put both `0.0` and `-0.0` in `Sign::Zero`. Wrapping a value in
+/// `SignBit` opts into `is_sign_negative()` instead: `-0.0` classifies as `Sign::Minus`, `0.0`
+/// as `Sign::Plus`, and no value is ever `Sign::Zero`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{svec, Sign, SignBit, SignVec};
+///
+/// let sv = svec![SignBit(0.0), SignBit(-0.0), SignBit(-1.5)];
+///
+/// assert_eq!(sv.count(Sign::Plus), 1);
+/// assert_eq!(sv.count(Sign::Minus), 2);
+/// assert_eq!(sv.count(Sign::Zero), 0);
+/// ```
+#[derive(Debug, Default, PartialEq, PartialOrd, Copy, Clone)]
+pub struct SignBit<T>(pub T);
+
+impl<T> SignBit<T> {
+    /// Unwraps the inner value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+macro_rules! signable_sign_bit {
+    ($($t:ty),*) => {$(
+        impl Signable for SignBit<$t> {
+            #[inline(always)]
+            fn sign(&self) -> Sign {
+                if self.0.is_sign_negative() {
+                    Sign::Minus
+                } else {
+                    Sign::Plus
+                }
+            }
+
+            #[inline(always)]
+            fn is_nan(&self) -> bool {
+                self.0.is_nan()
+            }
+        }
+
+        impl From<$t> for SignBit<$t> {
+            fn from(num: $t) -> Self {
+                SignBit(num)
+            }
+        }
+
+        impl From<SignBit<$t>> for Sign {
+            fn from(num: SignBit<$t>) -> Self {
+                num.sign()
+            }
+        }
+    )*};
+}
+
+macro_rules! signable_delegate {
+    ($($t:ident),*) => {$(
+        impl<T> Signable for $t<T>
+        where
+            T: Signable + ?Sized,
+        {
+            #[inline(always)]
+            fn sign(&self) -> Sign {
+                (**self).sign()
+            }
+
+            #[inline(always)]
+            fn is_nan(&self) -> bool {
+                (**self).is_nan()
+            }
+        }
+    )*};
+}
+
+macro_rules! signable_wrapper {
+    ($($t:ident),*) => {$(
+        impl<T> Signable for $t<T>
+        where
+            T: Signable,
+        {
+            #[inline(always)]
+            fn sign(&self) -> Sign {
+                self.0.sign()
+            }
+
+            #[inline(always)]
+            fn is_nan(&self) -> bool {
+                self.0.is_nan()
+            }
+        }
+    )*};
+}
+
+macro_rules! signable_nonzero {
+    ($($t:ty),*) => {$(
+        impl Signable for $t {
+            #[inline(always)]
+            fn sign(&self) -> Sign {
+                self.get().sign()
+            }
+        }
+    )*};
+}
+
+impl<T> Signable for &T
+where
+    T: Signable + ?Sized,
+{
+    #[inline(always)]
+    fn sign(&self) -> Sign {
+        (**self).sign()
+    }
+
+    #[inline(always)]
+    fn is_nan(&self) -> bool {
+        (**self).is_nan()
+    }
+}
+
+/// `Reverse` inverts the sign of the wrapped value, mirroring how it inverts its ordering.
+impl<T> Signable for Reverse<T>
+where
+    T: Signable,
+{
+    #[inline(always)]
+    fn sign(&self) -> Sign {
+        self.0.sign().flip()
+    }
+
+    #[inline(always)]
+    fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+/// `None` classifies as `Sign::Zero`. Wrap the option in `NoneAsPlus` or `NoneAsMinus` to
+/// classify `None` as `Sign::Plus` or `Sign::Minus` instead.
+impl<T> Signable for Option<T>
+where
+    T: Signable,
+{
+    #[inline(always)]
+    fn sign(&self) -> Sign {
+        self.as_ref().map_or(Sign::Zero, Signable::sign)
+    }
+
+    #[inline(always)]
+    fn is_nan(&self) -> bool {
+        self.as_ref().is_some_and(Signable::is_nan)
+    }
+}
+
+macro_rules! signable_none_as {
+    ($($(#[$doc:meta])* $t:ident => $sign:expr),*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Default, PartialEq, PartialOrd, Copy, Clone)]
+        pub struct $t<T>(pub Option<T>);
+
+        impl<T> $t<T> {
+            /// Unwraps the inner option.
+            #[inline(always)]
+            pub fn into_inner(self) -> Option<T> {
+                self.0
+            }
+        }
+
+        impl<T> Signable for $t<T>
+        where
+            T: Signable,
+        {
+            #[inline(always)]
+            fn sign(&self) -> Sign {
+                self.0.as_ref().map_or($sign, Signable::sign)
+            }
+
+            #[inline(always)]
+            fn is_nan(&self) -> bool {
+                self.0.is_nan()
+            }
+        }
+
+        impl<T> From<Option<T>> for $t<T> {
+            fn from(option: Option<T>) -> Self {
+                $t(option)
+            }
+        }
+    )*};
+}
+
+signable_none_as!(
+    /// Wrapper classifying `None` as `Sign::Plus`, and `Some` by the wrapped value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{svec, NoneAsPlus, Sign, SignVec};
+    ///
+    /// let sv = svec![NoneAsPlus(Some(-2)), NoneAsPlus(None), NoneAsPlus(Some(0))];
+    ///
+    /// assert_eq!(sv.count(Sign::Plus), 1);
+    /// assert_eq!(sv.count(Sign::Minus), 1);
+    /// assert_eq!(sv.count(Sign::Zero), 1);
+    /// ```
+    NoneAsPlus => Sign::Plus,
+    /// Wrapper classifying `None` as `Sign::Minus`, and `Some` by the wrapped value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{svec, NoneAsMinus, Sign, SignVec};
+    ///
+    /// let sv = svec![NoneAsMinus(Some(3)), NoneAsMinus(None)];
+    ///
+    /// assert_eq!(sv.count(Sign::Plus), 1);
+    /// assert_eq!(sv.count(Sign::Minus), 1);
+    /// ```
+    NoneAsMinus => Sign::Minus
+);
+
+/// `Greater` is `Sign::Plus`, `Less` is `Sign::Minus` and `Equal` is `Sign::Zero`.
+impl Signable for Ordering {
+    #[inline(always)]
+    fn sign(&self) -> Sign {
+        match self {
+            Ordering::Greater => Sign::Plus,
+            Ordering::Less => Sign::Minus,
+            Ordering::Equal => Sign::Zero,
+        }
+    }
+}
+
+/// Durations are never negative, so every `Duration`, `Duration::ZERO` included, is
+/// `Sign::Plus`.
+impl Signable for Duration {
+    #[inline(always)]
+    fn sign(&self) -> Sign {
+        Sign::Plus
+    }
+}
+
+signfrom!(i8, i16, i32, i64, i128, isize);
+signable!(i8, i16, i32, i64, i128, isize);
+signfrom_float!(f32, f64);
+signable_float!(f32, f64);
+signable_sign_bit!(f32, f64);
+signable_delegate!(Box, Rc, Arc);
+signable_wrapper!(Wrapping, Saturating);
+signable_nonzero!(
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroIsize
+);
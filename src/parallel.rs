@@ -0,0 +1,208 @@
+//! Parallel construction and iteration with rayon, behind the `rayon` cargo feature.
+
+use crate::signvec::Bucket;
+use crate::{IndexBackend, Sign, SignVec, Signable};
+use rayon::prelude::*;
+
+/// The number of elements each task classifies in `par_sync`.
+const CHUNK_LEN: usize = 1 << 16;
+
+impl<T, S> SignVec<T, S>
+where
+    T: Signable + Clone + Send + Sync,
+    S: IndexBackend + Send + Sync,
+{
+    /// Synchronizes the index sets with the vector's elements, like `sync`, classifying the
+    /// elements in parallel.
+    ///
+    /// The vector is split into chunks, each classified on its own into per-bucket lists of
+    /// offsets. The four sets are then cleared and refilled concurrently from the lists, chunk by
+    /// chunk, so no set is touched until every element has been classified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is a NaN and the policy is `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec};
+    ///
+    /// let mut sign_vec: SignVec<i64> = (-500_000..500_000).rev().collect();
+    /// sign_vec.par_sync();
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Minus), 500_000);
+    /// assert!(sign_vec.indices(Sign::Zero).contains(&499_999));
+    /// ```
+    pub fn par_sync(&mut self) {
+        let rules = self.rules();
+        let chunks: Vec<[Vec<u32>; 4]> = self
+            .vals
+            .par_chunks(CHUNK_LEN)
+            .map(|vals| {
+                let mut lists: [Vec<u32>; 4] = Default::default();
+                for (offset, val) in vals.iter().enumerate() {
+                    lists[rules.bucket(val) as usize].push(offset as u32);
+                }
+                lists
+            })
+            .collect();
+        let fill = |set: &mut S, bucket: Bucket| {
+            set.clear();
+            for (chunk, lists) in chunks.iter().enumerate() {
+                let base = chunk * CHUNK_LEN;
+                for &offset in &lists[bucket as usize] {
+                    set.insert(base + offset as usize);
+                }
+            }
+        };
+        let (pos, neg, zero, nan) = (&mut self.pos, &mut self.neg, &mut self.zero, &mut self.nan);
+        rayon::join(
+            || rayon::join(|| fill(pos, Bucket::Plus), || fill(neg, Bucket::Minus)),
+            || rayon::join(|| fill(zero, Bucket::Zero), || fill(nan, Bucket::NaN)),
+        );
+    }
+
+    /// Returns a parallel iterator over the indices of the elements with the specified sign, in
+    /// ascending order.
+    ///
+    /// The positions of the vector are split among the threads and each keeps those in the
+    /// bucket, so this suits buckets holding a sizable share of the elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use signvec::{svec, Sign, SignVec};
+    ///
+    /// let sign_vec = svec![5, -10, 15, 0];
+    /// let indices: Vec<usize> = sign_vec.par_indices(Sign::Plus).collect();
+    ///
+    /// assert_eq!(indices, vec![0, 2]);
+    /// ```
+    pub fn par_indices(&self, sign: Sign) -> impl ParallelIterator<Item = usize> + '_ {
+        let set = self.indices(sign);
+        (0..self.vals.len())
+            .into_par_iter()
+            .filter(move |idx| set.contains(idx))
+    }
+
+    /// Returns a parallel iterator over the elements with the specified sign, in index order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use signvec::{svec, Sign, SignVec};
+    ///
+    /// let sign_vec = svec![5, -10, 15, -20];
+    /// let total: i32 = sign_vec.par_values(Sign::Minus).sum();
+    ///
+    /// assert_eq!(total, -30);
+    /// ```
+    pub fn par_values(&self, sign: Sign) -> impl ParallelIterator<Item = &T> + '_ {
+        let set = self.indices(sign);
+        self.vals
+            .par_iter()
+            .enumerate()
+            .filter(move |(idx, _)| set.contains(idx))
+            .map(|(_, val)| val)
+    }
+}
+
+impl<T, S> FromParallelIterator<T> for SignVec<T, S>
+where
+    T: Signable + Clone + Send + Sync,
+    S: IndexBackend + Send + Sync,
+{
+    /// Collects the elements in parallel and classifies them with `par_sync`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use signvec::{Sign, SignVec};
+    ///
+    /// let sign_vec: SignVec<f64> = (0..1000).into_par_iter().map(|i| i as f64 - 499.5).collect();
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Minus), 500);
+    /// ```
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        let mut sign_vec = Self::with_backend(0);
+        sign_vec.vals = par_iter.into_par_iter().collect();
+        sign_vec.reserve_indices();
+        sign_vec.par_sync();
+        sign_vec
+    }
+}
+
+impl<T, S> IntoParallelIterator for SignVec<T, S>
+where
+    T: Signable + Clone + Send,
+    S: IndexBackend,
+{
+    type Item = T;
+    type Iter = rayon::vec::IntoIter<T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.vals.into_par_iter()
+    }
+}
+
+/// Provides `par_iter` on a `SignVec`, through rayon's `IntoParallelRefIterator`.
+impl<'a, T, S> IntoParallelIterator for &'a SignVec<T, S>
+where
+    T: Signable + Clone + Sync,
+    S: IndexBackend,
+{
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.vals.par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitmapIndex, SignVec32};
+    use fastset::Set;
+    use nanorand::{Rng, WyRand};
+
+    #[test]
+    fn test_par_sync() {
+        let mut rng = WyRand::new_seed(17);
+        let vals: Vec<f64> = (0..3 * CHUNK_LEN + 7)
+            .map(|i| match rng.generate_range(0..5u8) {
+                0 => 0.0,
+                1 => f64::NAN,
+                _ => i as f64 - rng.generate::<u32>() as f64 / 65536.0,
+            })
+            .collect();
+        let serial: SignVec<f64> = vals.iter().collect();
+        let parallel: SignVec<f64> = vals.clone().into_par_iter().collect();
+        let mut bitmap: SignVec<f64, BitmapIndex> = SignVec::with_backend(0);
+        bitmap.vals = vals.clone();
+        bitmap.par_sync();
+        let mut compact: SignVec32<f64> = SignVec32::with_backend(0);
+        compact.vals = vals;
+        compact.par_sync();
+
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(parallel.indices(sign), serial.indices(sign));
+            assert_eq!(bitmap.indices(sign), &Set::from(serial.indices(sign)));
+            assert_eq!(compact.count(sign), serial.count(sign));
+            let indices: Vec<usize> = parallel.par_indices(sign).collect();
+            assert!(indices.windows(2).all(|w| w[0] < w[1]));
+            assert!(indices.iter().all(|i| bitmap.indices(sign).contains(i)));
+            assert_eq!(indices.len(), serial.count(sign));
+        }
+        assert_eq!(parallel.indices_nan(), serial.indices_nan());
+        assert_eq!(parallel.par_iter().count(), serial.len());
+        assert_eq!(
+            parallel.par_values(Sign::Minus).count(),
+            serial.count(Sign::Minus)
+        );
+    }
+}
@@ -1,21 +1,250 @@
-use crate::{Sign, Signable};
-use fastset::Set;
+use crate::{IndexBackend, IndexSet, Sign, SignMasks, Signable};
 use nanorand::WyRand;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
-use std::ops::{Bound, Deref, Index, RangeBounds};
+use std::ops::{Bound, Deref, DerefMut, Index, Neg, RangeBounds};
 
-const DEFAULT_SET_SIZE: usize = 1000;
+/// Policy deciding how a `SignVec` handles values for which `Signable::is_nan` returns `true`.
+///
+/// Left unchecked, a NaN compares false against zero and would silently end up in one of the
+/// sign buckets, skewing counts and random selections.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum NanPolicy {
+    /// NaNs are refused. Panicking mutators such as `push` panic, while the `try_*` variants
+    /// return `SignVecError::NanRejected`.
+    Reject,
+    /// NaNs are kept out of the sign buckets and tracked in the dedicated `nan` set.
+    #[default]
+    Track,
+    /// NaNs are classified by their IEEE sign bit, as reported by `Signable::sign`.
+    SignBit,
+}
+
+/// Errors returned by the fallible `SignVec` operations.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SignVecError {
+    /// A NaN was offered to a `SignVec` whose policy is `NanPolicy::Reject`.
+    NanRejected {
+        /// The index the value was destined for.
+        index: usize,
+    },
+    /// An index was outside the bounds of the `SignVec`.
+    IndexOutOfBounds {
+        /// The offending index.
+        index: usize,
+        /// The length of the `SignVec`.
+        len: usize,
+    },
+    /// The `SignVec` would grow beyond the length its index backend can address, such as
+    /// `u32::MAX` elements for a `SignVec32`.
+    LengthLimitExceeded {
+        /// The largest length the backend supports.
+        max_len: usize,
+    },
+}
+
+impl fmt::Display for SignVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignVecError::NanRejected { index } => {
+                write!(f, "NaN rejected by NanPolicy::Reject at index {}", index)
+            }
+            SignVecError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index out of bounds: index {} length {}", index, len)
+            }
+            SignVecError::LengthLimitExceeded { max_len } => {
+                write!(f, "Length limit of the index backend exceeded: {}", max_len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignVecError {}
+
+/// The index set an element is filed under.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum Bucket {
+    Plus,
+    Minus,
+    Zero,
+    NaN,
+}
+
+impl From<Sign> for Bucket {
+    #[inline(always)]
+    fn from(sign: Sign) -> Self {
+        match sign {
+            Sign::Plus => Bucket::Plus,
+            Sign::Minus => Bucket::Minus,
+            Sign::Zero => Bucket::Zero,
+        }
+    }
+}
+
+/// The rules classifying the elements of a `SignVec`.
+///
+/// Copied out of the vector so that they can be applied while its fields are borrowed.
+pub(crate) struct Rules<T> {
+    nan_policy: NanPolicy,
+    classifier: Option<fn(&T) -> Sign>,
+}
+
+impl<T> Clone for Rules<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Rules<T> {}
+
+impl<T> Rules<T>
+where
+    T: Signable,
+{
+    /// Returns the bucket `val` belongs in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `val` is a NaN and the policy is `NanPolicy::Reject`.
+    #[inline(always)]
+    pub(crate) fn bucket(&self, val: &T) -> Bucket {
+        if val.is_nan() {
+            match self.nan_policy {
+                NanPolicy::Reject => panic!("NaN rejected by NanPolicy::Reject"),
+                NanPolicy::Track => Bucket::NaN,
+                NanPolicy::SignBit => val.sign().into(),
+            }
+        } else {
+            match self.classifier {
+                Some(classifier) => classifier(val).into(),
+                None => val.sign().into(),
+            }
+        }
+    }
+
+    /// Classifies `chunk`, at most 64 elements, into bit masks, before the `NanPolicy` applies.
+    ///
+    /// With no custom classifier this is `Signable::sign_masks`.
+    #[inline(always)]
+    fn masks(&self, chunk: &[T]) -> SignMasks {
+        match self.classifier {
+            Some(classifier) => {
+                let mut masks = SignMasks::default();
+                for (i, val) in chunk.iter().enumerate() {
+                    match val.is_nan() {
+                        true => masks.nan |= 1 << i,
+                        false => match classifier(val) {
+                            Sign::Plus => masks.plus |= 1 << i,
+                            Sign::Minus => masks.minus |= 1 << i,
+                            Sign::Zero => {}
+                        },
+                    }
+                }
+                masks
+            }
+            None => T::sign_masks(chunk),
+        }
+    }
+
+    /// Files `chunk`, at most 64 elements whose first one sits at index `base`, into `sets`,
+    /// given in the order of the `Bucket` variants.
+    ///
+    /// The chunk is classified by `masks` and each set takes its members straight from a bit
+    /// mask. Every element is classified before any set is touched, so a rejected NaN leaves
+    /// the sets as they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk holds a NaN and the policy is `NanPolicy::Reject`.
+    #[inline(always)]
+    fn file<S: IndexBackend>(&self, chunk: &[T], base: usize, sets: [&mut S; 4]) {
+        let mut masks = self.masks(chunk);
+        if masks.nan != 0 {
+            match self.nan_policy {
+                NanPolicy::Reject => panic!("NaN rejected by NanPolicy::Reject"),
+                NanPolicy::Track => {}
+                NanPolicy::SignBit => {
+                    let mut nans = masks.nan;
+                    while nans != 0 {
+                        let i = nans.trailing_zeros() as usize;
+                        nans &= nans - 1;
+                        match chunk[i].sign() {
+                            Sign::Plus => masks.plus |= 1 << i,
+                            Sign::Minus => masks.minus |= 1 << i,
+                            Sign::Zero => {}
+                        }
+                    }
+                    masks.nan = 0;
+                }
+            }
+        }
+        let zero = masks.zero(chunk.len());
+        let [pos, neg, zeros, nan] = sets;
+        pos.insert_mask(base, masks.plus);
+        neg.insert_mask(base, masks.minus);
+        zeros.insert_mask(base, zero);
+        nan.insert_mask(base, masks.nan);
+    }
+}
+
+/// An open interval around zero inside which elements keep the side they were on.
+#[derive(Debug, Clone)]
+struct DeadBand<T> {
+    low: T,
+    high: T,
+    // Captured where `T: PartialOrd` is known, since `SignVec` itself does not require it.
+    contains: fn(&T, &T, &T) -> bool,
+}
+
+impl<T> DeadBand<T> {
+    #[inline(always)]
+    fn contains(&self, val: &T) -> bool {
+        (self.contains)(&self.low, &self.high, val)
+    }
+}
+
+fn band_contains<T: PartialOrd>(low: &T, high: &T, val: &T) -> bool {
+    low < val && val < high
+}
+
+/// Resolves a `Bucket` to the matching index set of a `SignVec`.
+///
+/// A macro rather than a method so that the borrow stays confined to the one set, leaving the
+/// other fields (typically `vals`) available to the caller.
+macro_rules! bucket {
+    ($sv:expr, $bucket:expr) => {
+        match $bucket {
+            Bucket::Plus => &mut $sv.pos,
+            Bucket::Minus => &mut $sv.neg,
+            Bucket::Zero => &mut $sv.zero,
+            Bucket::NaN => &mut $sv.nan,
+        }
+    };
+}
 
 /// A vector-like data structure with additional information about the sign of its elements.
 ///
-/// This data structure holds a vector of elements of type `T`, along with sets `pos` and `neg`
-/// containing the indices of positive and negative elements respectively. The `SignVec` is used
-/// to efficiently store and manipulate elements based on their sign.
+/// This data structure holds a vector of elements of type `T`, along with sets `pos`, `neg` and
+/// `zero` containing the indices of positive, negative and zero elements respectively. The
+/// `SignVec` is used to efficiently store and manipulate elements based on their sign.
+///
+/// Elements are classified by `Signable::sign` unless a different classifier is supplied
+/// through `with_classifier` or `set_classifier`.
+///
+/// An optional dead band, set with `with_hysteresis` or `set_hysteresis`, keeps noisy values
+/// from flipping back and forth around zero: an updated element only changes sides once it
+/// reaches `+eps` or `-eps`.
+///
+/// Values reporting `Signable::is_nan` are handled according to the vector's `NanPolicy`. Under
+/// the default `NanPolicy::Track` they are filed in a fourth set, `nan`, and belong to none of
+/// the sign buckets.
 ///
 /// Compared to standard vectors, `SignVec` provides additional functionality for handling
 /// elements based on their sign and maintaining sets of positive and negative indices.
@@ -23,29 +252,117 @@ const DEFAULT_SET_SIZE: usize = 1000;
 /// # Type Parameters
 ///
 /// * `T`: The type of elements stored in the `SignVec`, which must implement the `Signable` trait
-///        and also be cloneable.
+///   and also be cloneable.
+/// * `S`: The `IndexBackend` storing the index sets, `IndexSet` unless specified otherwise.
 ///
 /// # Fields
 ///
+/// The fields are private, so that the index sets cannot fall out of step with the elements.
+/// The elements are read through `as_slice` or `Deref`, the sets through `indices` and its
+/// variants. `raw_parts_mut` and `from_raw_parts` give unchecked access for the rare cases that
+/// need it.
+///
 /// * `vals`: A vector holding elements of type `T`.
 /// * `pos`: A set containing the indices of positive elements in `vals`.
 /// * `neg`: A set containing the indices of negative elements in `vals`.
+/// * `zero`: A set containing the indices of zero elements in `vals`.
+/// * `nan`: A set containing the indices of NaN elements tracked under `NanPolicy::Track`.
+/// * `nan_policy`: The policy applied to NaN values.
+/// * `classifier`: The function deciding the sign of non-NaN elements.
+/// * `dead_band`: The optional hysteresis band.
 /// * `_marker`: Phantom data field to maintain covariance with the type parameter `T`.
 ///
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SignVec<T>
+/// # Serialization
+///
+/// Only `vals` and `nan_policy` are serialized. The classifier and the hysteresis band are not
+/// preserved: deserializing rebuilds the index sets from the elements with `Signable::sign`,
+/// without either, so elements the band held in a bucket may land in another one. It fails if the
+/// policy is `NanPolicy::Reject` and an element is a NaN. Data written before `nan_policy` was
+/// added, with `pos` and `neg` next to `vals`, is still accepted and read with the default
+/// `NanPolicy::Track`.
+#[derive(Debug, Clone)]
+pub struct SignVec<T, S = IndexSet>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
-    pub vals: Vec<T>,
-    pub pos: Set,
-    pub neg: Set,
+    pub(crate) vals: Vec<T>,
+    pub(crate) pos: S,
+    pub(crate) neg: S,
+    pub(crate) zero: S,
+    pub(crate) nan: S,
+    nan_policy: NanPolicy,
+    classifier: Option<fn(&T) -> Sign>,
+    dead_band: Option<DeadBand<T>>,
     _marker: PhantomData<T>,
 }
 
-impl<T> SignVec<T>
+impl<T, S> Serialize for SignVec<T, S>
+where
+    T: Signable + Clone + Serialize,
+    S: IndexBackend,
+{
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        let mut state = serializer.serialize_struct("SignVec", 2)?;
+        state.serialize_field("vals", &self.vals)?;
+        state.serialize_field("nan_policy", &self.nan_policy)?;
+        state.end()
+    }
+}
+
+/// The serialized form of a `SignVec`.
+#[derive(Deserialize)]
+#[serde(rename = "SignVec")]
+struct SignVecRepr<T> {
+    vals: Vec<T>,
+    #[serde(default)]
+    nan_policy: NanPolicy,
+}
+
+impl<'de, T, S> Deserialize<'de> for SignVec<T, S>
+where
+    T: Signable + Clone + Deserialize<'de>,
+    S: IndexBackend,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SignVecRepr { vals, nan_policy } = SignVecRepr::<T>::deserialize(deserializer)?;
+        Self::with_backend(0)
+            .check_len(vals.len())
+            .map_err(de::Error::custom)?;
+        if nan_policy == NanPolicy::Reject {
+            if let Some(index) = vals.iter().position(|val| val.is_nan()) {
+                return Err(de::Error::custom(SignVecError::NanRejected { index }));
+            }
+        }
+        Ok(Self::from_vals(vals, nan_policy))
+    }
+}
+
+/// A `SignVec` storing its indices as `u32`, which halves the memory of the index sets.
+///
+/// It offers the same methods as `SignVec`, and is built with `with_backend`, `collect` or
+/// `extend`. The vector holds fewer than `u32::MAX` elements: `push` and `insert` panic beyond
+/// that, while `try_push` and `try_extend_from_slice` return
+/// `SignVecError::LengthLimitExceeded`.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{Sign, SignVec32};
+///
+/// let mut sign_vec: SignVec32<f64> = SignVec32::with_backend(16);
+/// sign_vec.extend([1.0, -2.0, 3.0]);
+/// sign_vec.set(0, -1.0);
+///
+/// assert_eq!(sign_vec.count(Sign::Minus), 2);
+/// assert!(sign_vec.indices(Sign::Plus).contains(&2));
+/// ```
+pub type SignVec32<T> = SignVec<T, IndexSet<u32>>;
+
+impl<T, S> SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Appends elements from another vector to the end of this `SignVec`.
     ///
@@ -78,13 +395,12 @@ where
     where
         T: Signable + Clone,
     {
+        let rules = self.rules();
         let start_len = self.vals.len();
+        self.grow(other.len());
         other.iter().enumerate().for_each(|(index, e)| {
             let vals_index = start_len + index;
-            match e.sign() {
-                Sign::Plus => self.pos.insert(vals_index),
-                Sign::Minus => self.neg.insert(vals_index),
-            };
+            bucket!(self, rules.bucket(e)).insert(vals_index);
             self.vals.push(e.clone());
         });
     }
@@ -130,6 +446,48 @@ where
         self.vals.as_slice()
     }
 
+    /// Starts a bulk update, deferring the index sets until it is committed.
+    ///
+    /// The returned `SignVecBulk` writes straight into the elements and records which indices
+    /// it handed out. Committing it, or dropping it, reclassifies only those indices, so a phase
+    /// of many writes costs one set update per touched element rather than one per write, or a
+    /// full `sync`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15, -20];
+    /// let mut bulk = sign_vec.begin_bulk();
+    /// bulk.set(0, -5);
+    /// for x in bulk.slice_mut(2..4) {
+    ///     *x = -*x;
+    /// }
+    /// bulk.commit().unwrap();
+    ///
+    /// assert_eq!(sign_vec, svec![-5, -10, -15, 20]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 3);
+    /// ```
+    #[inline(always)]
+    pub fn begin_bulk(&mut self) -> SignVecBulk<'_, T, S> {
+        let vals = std::mem::take(&mut self.vals);
+        let sets = [
+            std::mem::take(&mut self.pos),
+            std::mem::take(&mut self.neg),
+            std::mem::take(&mut self.zero),
+            std::mem::take(&mut self.nan),
+        ];
+        SignVecBulk {
+            sign_vec: self,
+            vals,
+            sets: Some(sets),
+            dirty: Vec::new(),
+            saved: Vec::new(),
+            all: false,
+        }
+    }
+
     /// Returns the capacity of the `vals` vector of this `SignVec`.
     ///
     /// This method returns the capacity of the `vals` vector, which is the maximum number of elements
@@ -141,17 +499,66 @@ where
     /// use signvec::{SignVec, svec};
     ///
     /// let sv = svec![5, -10, 15];
-    /// assert_eq!(sv.capacity(), 4);
+    /// assert_eq!(sv.capacity(), 3);
     /// ```
     #[inline(always)]
     pub fn capacity(&self) -> usize {
         self.vals.capacity()
     }
 
+    /// Returns the number of elements the index sets can address without reallocating.
+    ///
+    /// The index sets follow `vals`: whenever the vector grows, they are reserved up to its new
+    /// capacity, so this is at least `capacity()` after any growth. `shrink_to_fit` and
+    /// `shrink_to` shrink them down to the length again, and `clear` and `truncate` keep them, as
+    /// they keep the capacity of `vals`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::SignVec;
+    ///
+    /// let mut sign_vec = SignVec::new();
+    /// for i in 0..5000 {
+    ///     sign_vec.push(i - 2500);
+    /// }
+    /// assert!(sign_vec.index_capacity() >= sign_vec.capacity());
+    ///
+    /// sign_vec.truncate(10);
+    /// sign_vec.shrink_to_fit();
+    /// assert!(sign_vec.index_capacity() >= 10);
+    /// assert!(sign_vec.index_capacity() < 5000);
+    /// ```
+    #[inline(always)]
+    pub fn index_capacity(&self) -> usize {
+        self.pos
+            .capacity()
+            .min(self.neg.capacity())
+            .min(self.zero.capacity())
+            .min(self.nan.capacity())
+    }
+
+    /// Returns the function classifying the non-NaN elements of this `SignVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let sign_vec = svec![5, -10];
+    /// let classify = sign_vec.classifier();
+    ///
+    /// assert_eq!(classify(&-3), Sign::Minus);
+    /// ```
+    #[inline(always)]
+    pub fn classifier(&self) -> fn(&T) -> Sign {
+        self.classifier.unwrap_or(T::sign)
+    }
+
     /// Clears all elements from this `SignVec`.
     ///
     /// This method removes all elements from the `vals` vector of this `SignVec`, and clears the
-    /// `pos` and `neg` sets. The capacity of none of the fields are affected.
+    /// `pos`, `neg`, `zero` and `nan` sets. The capacity of none of the fields are affected.
     ///
     /// # Examples
     ///
@@ -168,12 +575,15 @@ where
         self.vals.clear();
         self.pos.clear();
         self.neg.clear();
+        self.zero.clear();
+        self.nan.clear();
     }
 
     /// Returns the number of elements with the specified sign in this `SignVec`.
     ///
-    /// This method returns the number of elements in the `pos` set if `sign` is `Sign::Plus`, or
-    /// the number of elements in the `neg` set if `sign` is `Sign::Minus`.
+    /// This method returns the number of elements in the `pos` set if `sign` is `Sign::Plus`, the
+    /// number of elements in the `neg` set if `sign` is `Sign::Minus`, or the number of elements
+    /// in the `zero` set if `sign` is `Sign::Zero`.
     ///
     /// # Arguments
     ///
@@ -184,16 +594,18 @@ where
     /// ```
     /// use signvec::{SignVec, Sign, svec};
     ///
-    /// let sv = svec![5, -10, 15];
+    /// let sv = svec![5, -10, 15, 0];
     ///
     /// assert_eq!(sv.count(Sign::Plus), 2);
     /// assert_eq!(sv.count(Sign::Minus), 1);
+    /// assert_eq!(sv.count(Sign::Zero), 1);
     /// ```
     #[inline(always)]
     pub fn count(&self, sign: Sign) -> usize {
         match sign {
             Sign::Plus => self.pos.len(),
             Sign::Minus => self.neg.len(),
+            Sign::Zero => self.zero.len(),
         }
     }
 
@@ -235,6 +647,44 @@ where
         self.neg.len()
     }
 
+    /// Returns the number of elements equal to zero in this `SignVec`.
+    ///
+    /// This method directly returns the number of elements in the `zero` set,
+    /// providing a more straightforward and slightly faster alternative to calling `count(Sign::Zero)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    ///
+    /// let sv = svec![5, 0, -10, 0];
+    ///
+    /// assert_eq!(sv.count_zero(), 2);
+    /// ```
+    #[inline(always)]
+    pub fn count_zero(&self) -> usize {
+        self.zero.len()
+    }
+
+    /// Returns the number of NaN elements tracked in this `SignVec`.
+    ///
+    /// NaNs are only tracked under `NanPolicy::Track`, so this is always zero under the other
+    /// policies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    ///
+    /// let sv = svec![5.0, f64::NAN, -10.0];
+    ///
+    /// assert_eq!(sv.count_nan(), 1);
+    /// ```
+    #[inline(always)]
+    pub fn count_nan(&self) -> usize {
+        self.nan.len()
+    }
+
     /// Removes consecutive duplicate elements from this `SignVec`.
     ///
     /// This method removes consecutive duplicate elements from the `vals` vector of this `SignVec`.
@@ -255,41 +705,17 @@ where
     where
         T: PartialEq + Signable,
     {
-        if self.vals.is_empty() {
-            return;
-        }
-
-        let mut write = 1; // Index to write to.
-        for read in 1..self.vals.len() {
-            if self.vals[read] != self.vals[read - 1] {
-                // Move non-duplicate to the 'write' position if necessary.
-                if read != write {
-                    self.vals[write] = self.vals[read].clone();
-
-                    if self.vals[read].sign() == Sign::Plus {
-                        self.pos.remove(&read);
-                        self.pos.insert(write);
-                    } else {
-                        self.neg.remove(&read);
-                        self.neg.insert(write);
-                    }
-                }
-                write += 1;
-            } else {
-                // For duplicates, just remove them from pos and neg sets.
-                self.pos.remove(&read);
-                self.neg.remove(&read);
-            }
-        }
-        // Truncate the vector to remove excess elements.
-        self.vals.truncate(write);
+        self.dedup_by(|a, b| a == b);
     }
 
-    /// Removes elements from this `SignVec` based on a predicate.
+    /// Removes consecutive elements from this `SignVec` based on a predicate.
     ///
     /// This method removes elements from the `vals` vector of this `SignVec` based on the provided
-    /// predicate `same_bucket`. Elements `x` and `y` are considered duplicates if `same_bucket(&x, &y)`
-    /// returns `true`.
+    /// predicate `same_bucket`. It is passed each element and the last element kept before it,
+    /// and the element is removed if `same_bucket(&element, &kept)` returns `true`.
+    ///
+    /// Like `retain`, this is a single pass that renumbers the index sets as the elements are
+    /// compacted. The elements that stay keep their buckets and are not classified again.
     ///
     /// # Arguments
     ///
@@ -300,43 +726,37 @@ where
     /// ```
     /// use signvec::{SignVec, svec};
     ///
-    /// let mut sign_vec = svec![5, 5, -10, 15, 15];
+    /// let mut sign_vec = svec![5, 5, -10, 15, 15, 5];
     /// sign_vec.dedup_by(|x, y| x == y);
     ///
-    /// assert_eq!(sign_vec, svec![5, -10, 15]);
+    /// assert_eq!(sign_vec, svec![5, -10, 15, 5]);
     /// ```
     #[inline(always)]
     pub fn dedup_by<F>(&mut self, mut same_bucket: F)
     where
         F: FnMut(&T, &T) -> bool,
     {
-        unsafe {
-            let mut len = self.vals.len();
-            let mut i = 0;
-            let vals_ptr = self.vals.as_mut_ptr();
-            while i < len {
-                let curr = vals_ptr.add(i);
-                let mut j = i + 1;
-                while j < len {
-                    let next = vals_ptr.add(j);
-                    if same_bucket(&*curr, &*next) {
-                        self.vals.remove(j);
-                        self.pos.remove(&j);
-                        self.neg.remove(&j);
-                        len -= 1;
-                    } else {
-                        j += 1;
-                    }
-                }
-                i += 1;
+        let len = self.vals.len();
+        let base = self.vals.as_mut_ptr();
+        let mut kept = 0;
+        let dup = |val: &mut T| {
+            // SAFETY: the elements kept so far have been moved to the front of the buffer by the
+            // time the next one is visited, so the last of them sits at `kept - 1`, before `val`.
+            if kept > 0 && same_bucket(val, unsafe { &*base.add(kept - 1) }) {
+                return true;
             }
-        }
+            kept += 1;
+            false
+        };
+        self.extract_with(0, len, dup, false).for_each(drop);
     }
-    /// Removes elements from this `SignVec` based on a key function.
+
+    /// Removes consecutive elements from this `SignVec` based on a key function.
     ///
     /// This method removes elements from the `vals` vector of this `SignVec` based on the key
     /// returned by the provided key function `key`. If the key of two consecutive elements is equal,
-    /// the second element is removed.
+    /// the second element is removed. As with `dedup_by`, the elements that stay keep their
+    /// buckets.
     ///
     /// # Arguments
     ///
@@ -358,24 +778,7 @@ where
         F: FnMut(&T) -> K,
         K: PartialEq,
     {
-        unsafe {
-            let mut i = 1;
-            let vals_ptr = self.vals.as_mut_ptr();
-            while i < self.vals.len() {
-                // Use while loop to manually control the iteration process, allowing us to adjust 'i' as needed.
-                let prev = vals_ptr.add(i - 1);
-                let now = vals_ptr.add(i);
-                if i > 0 && key(&*prev) == key(&*now) {
-                    self.vals.remove(i); // Remove the current item if its key matches the previous item's key.
-                                         // Do not increment 'i' so that the next element,
-                                         // which shifts into the current index, is compared next.
-                    self.pos.remove(&(i));
-                    self.neg.remove(&(i));
-                } else {
-                    i += 1; // Only increment 'i' if no removal was made.
-                }
-            }
-        }
+        self.dedup_by(|a, b| key(a) == key(b));
     }
 
     /// Drains elements from this `SignVec` based on a range.
@@ -383,6 +786,11 @@ where
     /// This method removes elements from the `vals` vector of this `SignVec` based on the provided
     /// range `range`. It returns a `SignVecDrain` iterator over the removed elements.
     ///
+    /// The range is taken out of the vector and the index sets are renumbered in one pass when
+    /// `drain` is called, so the `SignVec` is already in its final state while the iterator is
+    /// alive. Elements the iterator does not yield are dropped with it; if it is leaked, they
+    /// are leaked too, but the `SignVec` stays consistent.
+    ///
     /// # Arguments
     ///
     /// * `range`: The range of indices to drain elements from.
@@ -404,7 +812,7 @@ where
     /// assert_eq!(sign_vec, svec![5, 20]);
     /// ```
     #[inline(always)]
-    pub fn drain<R>(&mut self, range: R) -> SignVecDrain<'_, T>
+    pub fn drain<R>(&mut self, range: R) -> SignVecDrain<'_, T, S>
     where
         R: RangeBounds<usize>,
     {
@@ -424,13 +832,58 @@ where
             panic!("Drain range out of bounds");
         }
 
+        // Move the drained range behind the tail and hide it past the new length, where the
+        // iterator takes ownership of it.
+        let len = self.vals.len();
+        self.vals[start..].rotate_left(end - start);
+        let new_len = len - (end - start);
+        // SAFETY: `new_len <= len`, and the elements in `new_len..len` are now owned by the
+        // drain, which reads each of them at most once and drops the rest.
+        unsafe { self.vals.set_len(new_len) };
+        self.pos.remove_range_shift(start, end);
+        self.neg.remove_range_shift(start, end);
+        self.zero.remove_range_shift(start, end);
+        self.nan.remove_range_shift(start, end);
+
         SignVecDrain {
             sign_vec: self,
-            current_index: start,
-            drain_end: end,
+            front: new_len,
+            back: len,
         }
     }
 
+    /// Returns a guard for the element at `idx`, which files the element under its new sign when
+    /// dropped.
+    ///
+    /// This is the counterpart of `IndexMut`, which a `SignVec` cannot offer: a plain `&mut T`
+    /// could change the sign of the element without the index sets noticing. The guard takes the
+    /// index out of its set and dereferences to the element in place; on drop it files the index
+    /// under the new sign, keeping the old side inside the hysteresis band as `set` does. The
+    /// element is only cloned under `NanPolicy::Reject`, to restore it should it become a NaN.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds. Dropping the guard panics if the element became a NaN
+    /// and the policy is `NanPolicy::Reject`, in which case the element keeps its old value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// *sign_vec.entry(1) += 20;
+    ///
+    /// assert_eq!(sign_vec, svec![5, 10, 15]);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 3);
+    /// ```
+    #[inline(always)]
+    pub fn entry(&mut self, idx: usize) -> SignVecMut<'_, T, S> {
+        let len = self.vals.len();
+        self.get_mut(idx)
+            .unwrap_or_else(|| panic!("Index out of bounds: index {} length {}", idx, len))
+    }
+
     /// Extends this `SignVec` with elements from a slice.
     ///
     /// This method appends each element from the provided slice `other` to the end of the `vals`
@@ -453,13 +906,12 @@ where
     /// ```
     #[inline(always)]
     pub fn extend_from_slice(&mut self, other: &[T]) {
-        let offset = self.vals.len();
-        self.vals.extend_from_slice(other);
-        for (i, e) in other.iter().enumerate() {
-            match e.sign() {
-                Sign::Plus => self.pos.insert(offset + i),
-                Sign::Minus => self.neg.insert(offset + i),
-            };
+        let rules = self.rules();
+        self.grow(other.len());
+        for chunk in other.chunks(64) {
+            let sets = [&mut self.pos, &mut self.neg, &mut self.zero, &mut self.nan];
+            rules.file(chunk, self.vals.len(), sets);
+            self.vals.extend_from_slice(chunk);
         }
     }
 
@@ -508,62 +960,202 @@ where
         }
 
         let offset = self.vals.len();
+        self.grow(end - start);
         self.vals.extend_from_within(start..end);
         for i in start..end {
-            match self.vals[i].sign() {
-                Sign::Plus => self.pos.insert(offset + i - start),
-                Sign::Minus => self.neg.insert(offset + i - start),
-            };
+            // The copies share the bucket of their source element.
+            bucket!(self, self.bucket_of(i)).insert(offset + i - start);
         }
     }
-    /// Inserts an element at a specified index into this `SignVec`.
+
+    /// Removes the elements in `range` for which `filter` returns `true` and yields them.
     ///
-    /// This method inserts the specified `element` at the given `index` into the `vals` vector of
-    /// this `SignVec`. It updates the `pos` and `neg` sets accordingly based on the sign of the
-    /// inserted element.
+    /// Like `Vec::extract_if`, `filter` sees each element of the range once, in order, and may
+    /// mutate it. Elements that stay are reclassified, since `filter` may have changed them, and
+    /// are compacted and renumbered as the iterator advances. If the iterator is dropped before it
+    /// is exhausted, the elements it has not visited are kept.
+    ///
+    /// While the iterator is alive the `SignVec` appears empty. If the iterator is leaked, the
+    /// `SignVec` stays empty and its elements are leaked.
     ///
     /// # Arguments
     ///
-    /// * `index`: The index at which to insert the element.
-    /// * `element`: The element to insert.
+    /// * `range`: The range of indices to visit.
+    /// * `filter`: A closure returning `true` for the elements to extract.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
     ///
     /// # Examples
     ///
     /// ```
-    /// use signvec::{SignVec, svec};
+    /// use signvec::{SignVec, Sign, svec};
     ///
-    /// let mut sign_vec = svec![5, -10];
-    /// sign_vec.insert(1, 15);
+    /// let mut sign_vec = svec![5, -10, 15, -20, 25];
+    /// let negatives: Vec<_> = sign_vec.extract_if(.., |x| *x < 0).collect();
     ///
-    /// assert_eq!(sign_vec, svec![5, 15, -10]);
+    /// assert_eq!(negatives, vec![-10, -20]);
+    /// assert_eq!(sign_vec, svec![5, 15, 25]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 0);
     /// ```
     #[inline(always)]
-    pub fn insert(&mut self, index: usize, element: T) {
-        self.pos = self
-            .pos
-            .iter()
-            .map(|&idx| if idx >= index { idx + 1 } else { idx })
-            .collect();
-        self.neg = self
-            .neg
-            .iter()
-            .map(|&idx| if idx >= index { idx + 1 } else { idx })
-            .collect();
-        match element.sign() {
-            Sign::Plus => {
-                self.pos.insert(index);
-            }
-            Sign::Minus => {
-                self.neg.insert(index);
-            }
+    pub fn extract_if<F, R>(&mut self, range: R, filter: F) -> SignVecExtractIf<'_, T, F, S>
+    where
+        F: FnMut(&mut T) -> bool,
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s + 1,
+            Bound::Unbounded => 0,
         };
-        self.vals.insert(index, element);
+        let end = match range.end_bound() {
+            Bound::Included(&e) => e + 1,
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => self.vals.len(),
+        };
+        if start > end || end > self.vals.len() {
+            panic!("Extract range out of bounds");
+        }
+        self.extract_with(start, end, filter, true)
     }
 
-    /// Returns a reference to the set of indices with the specified sign.
+    /// Creates a `SignVec` from its elements and index sets, the latter in the order `pos`,
+    /// `neg`, `zero`, `nan`, without checking them.
     ///
-    /// This method returns a reference to the `Set` containing the indices of elements with the
-    /// specified `sign` in this `SignVec`.
+    /// The vector gets the default `NanPolicy` and classifier and no hysteresis band. Together
+    /// with `raw_parts_mut` this allows a `SignVec` to be rebuilt from sets kept elsewhere
+    /// without classifying the elements again.
+    ///
+    /// # Safety
+    ///
+    /// Every index below `vals.len()` must be in exactly one set, in the one `Signable::sign`
+    /// picks for its element, or in `nan` for a NaN, and no set may hold an index at or past
+    /// `vals.len()`. A `SignVec` built from sets breaking these rules may panic or return wrong
+    /// results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{IndexSet, Sign, SignVec};
+    ///
+    /// let sets = [
+    ///     IndexSet::from([1]),
+    ///     IndexSet::from([0]),
+    ///     IndexSet::new(),
+    ///     IndexSet::new(),
+    /// ];
+    /// let sign_vec = unsafe { SignVec::from_raw_parts(vec![-4, 4], sets) };
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    /// ```
+    #[inline(always)]
+    pub unsafe fn from_raw_parts(vals: Vec<T>, sets: [S; 4]) -> Self {
+        let [pos, neg, zero, nan] = sets;
+        Self {
+            vals,
+            pos,
+            neg,
+            zero,
+            nan,
+            nan_policy: NanPolicy::default(),
+            classifier: None,
+            dead_band: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a guard for the element at `idx`, or `None` if it is out of bounds.
+    ///
+    /// The guard dereferences to the element and, when dropped, files it under its new sign.
+    /// Each guard clones its element under `NanPolicy::Reject` only. See `entry` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// if let Some(mut x) = sign_vec.get_mut(0) {
+    ///     *x = -*x;
+    /// }
+    ///
+    /// assert!(sign_vec.get_mut(3).is_none());
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn get_mut(&mut self, idx: usize) -> Option<SignVecMut<'_, T, S>> {
+        if idx >= self.vals.len() {
+            return None;
+        }
+        // SAFETY: `idx` is in bounds, and the guard borrows the `SignVec` for its lifetime.
+        Some(unsafe { SignVecMut::new(self, idx, true) })
+    }
+
+    /// Inserts an element at a specified index into this `SignVec`.
+    ///
+    /// This method inserts the specified `element` at the given `index` into the `vals` vector of
+    /// this `SignVec`. It updates the `pos` and `neg` sets accordingly based on the sign of the
+    /// inserted element.
+    ///
+    /// Shifting the elements after `index` takes time linear in their number, as with
+    /// `Vec::insert`. The default `IndexSet` backend shifts its indices in O(√n) steps by rotating
+    /// blocks of about `√n` slots of its slot table, so the index sets add little to that.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index at which to insert the element.
+    /// * `element`: The element to insert.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`, or if the vector already holds as many elements as its index
+    /// backend can address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10];
+    /// sign_vec.insert(1, 15);
+    ///
+    /// assert_eq!(sign_vec, svec![5, 15, -10]);
+    /// ```
+    #[inline(always)]
+    pub fn insert(&mut self, index: usize, element: T) {
+        self.check_len(1).unwrap_or_else(|err| panic!("{}", err));
+        let bucket = self.rules().bucket(&element);
+        self.grow(1);
+        self.vals.insert(index, element);
+        self.pos.insert_shift(index);
+        self.neg.insert_shift(index);
+        self.zero.insert_shift(index);
+        self.nan.insert_shift(index);
+        bucket!(self, bucket).insert(index);
+    }
+
+    /// Returns the half-width `eps` of the hysteresis band, if one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::SignVec;
+    ///
+    /// let sign_vec = SignVec::with_hysteresis(0.1);
+    ///
+    /// assert_eq!(sign_vec.hysteresis(), Some(&0.1));
+    /// ```
+    #[inline(always)]
+    pub fn hysteresis(&self) -> Option<&T> {
+        self.dead_band.as_ref().map(|band| &band.high)
+    }
+
+    /// Returns a reference to the set of indices with the specified sign.
+    ///
+    /// This method returns a reference to the `IndexSet` containing the indices of elements with the
+    /// specified `sign` in this `SignVec`.
     ///
     /// # Arguments
     ///
@@ -581,16 +1173,17 @@ where
     /// assert_eq!(sign_vec.indices(Sign::Minus), &Set::from(&[1, 3]));
     /// ```
     #[inline(always)]
-    pub fn indices(&self, sign: Sign) -> &Set {
+    pub fn indices(&self, sign: Sign) -> &S {
         match sign {
             Sign::Plus => &self.pos,
             Sign::Minus => &self.neg,
+            Sign::Zero => &self.zero,
         }
     }
 
     /// Returns a reference to the set of indices with positive signs.
     ///
-    /// This method provides direct access to the `Set` containing the indices of elements
+    /// This method provides direct access to the `IndexSet` containing the indices of elements
     /// with a positive sign in this `SignVec`, bypassing the need to specify the sign.
     ///
     /// # Examples
@@ -604,13 +1197,13 @@ where
     /// assert_eq!(sign_vec.indices_pos(), &Set::from(&[0, 2]));
     /// ```
     #[inline(always)]
-    pub fn indices_pos(&self) -> &Set {
+    pub fn indices_pos(&self) -> &S {
         &self.pos
     }
 
     /// Returns a reference to the set of indices with negative signs.
     ///
-    /// This method provides direct access to the `Set` containing the indices of elements
+    /// This method provides direct access to the `IndexSet` containing the indices of elements
     /// with a negative sign in this `SignVec`, bypassing the need to specify the sign.
     ///
     /// # Examples
@@ -624,10 +1217,50 @@ where
     /// assert_eq!(sign_vec.indices_neg(), &Set::from(&[1, 3]));
     /// ```
     #[inline(always)]
-    pub fn indices_neg(&self) -> &Set {
+    pub fn indices_neg(&self) -> &S {
         &self.neg
     }
 
+    /// Returns a reference to the set of indices of elements equal to zero.
+    ///
+    /// This method provides direct access to the `IndexSet` containing the indices of elements
+    /// with a zero sign in this `SignVec`, bypassing the need to specify the sign.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    /// use fastset::Set;
+    ///
+    /// let sign_vec = svec![5, 0, 15, 0];
+    ///
+    /// assert_eq!(sign_vec.indices_zero(), &Set::from(&[1, 3]));
+    /// ```
+    #[inline(always)]
+    pub fn indices_zero(&self) -> &S {
+        &self.zero
+    }
+
+    /// Returns a reference to the set of indices of NaN elements.
+    ///
+    /// NaNs are only tracked under `NanPolicy::Track`, so this set is empty under the other
+    /// policies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    /// use fastset::Set;
+    ///
+    /// let sign_vec = svec![5.0, f64::NAN, 15.0];
+    ///
+    /// assert_eq!(sign_vec.indices_nan(), &Set::from(&[1]));
+    /// ```
+    #[inline(always)]
+    pub fn indices_nan(&self) -> &S {
+        &self.nan
+    }
+
     /// Consumes this `SignVec`, returning a boxed slice of its elements.
     ///
     /// This method consumes the `SignVec`, transforming it into a boxed slice of its elements.
@@ -665,6 +1298,32 @@ where
         self.vals.is_empty()
     }
 
+    /// Returns an iterator over guards for the elements, each filing its element under its new
+    /// sign when dropped.
+    ///
+    /// This is what iterating over `&mut SignVec` yields. Since every guard reclassifies its
+    /// element as it goes, a sweep over the whole vector keeps the index sets current in the
+    /// same pass, with no `sync` afterwards. The elements are not cloned, except under
+    /// `NanPolicy::Reject`, where each guard keeps a copy to restore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// for mut x in sign_vec.iter_mut_tracked() {
+    ///     *x -= 10;
+    /// }
+    ///
+    /// assert_eq!(sign_vec, svec![-5, -20, 5]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn iter_mut_tracked(&mut self) -> SignVecIterMut<'_, T, S> {
+        SignVecIterMut::new(self)
+    }
+
     /// Converts this `SignVec` into a mutable slice without deallocating memory.
     ///
     /// This method consumes the `SignVec` and returns a mutable reference to its elements without
@@ -706,36 +1365,99 @@ where
     pub fn len(&self) -> usize {
         self.vals.len()
     }
-    /// Creates a new `SignVec` from a slice of elements.
+
+    /// Replaces every element with `f` applied to it, filing each under its new sign.
     ///
-    /// This method constructs a new `SignVec` by iterating over the elements in the input slice `input`.
-    /// It initializes the `vals` vector with the elements from the slice and populates the `pos` and
-    /// `neg` sets based on the sign of each element. The maximum index value is used to initialize the sets.
+    /// This is `update_all` for a closure mapping each value to its successor, and shares its
+    /// single pass: only the elements whose sign changed move between sets.
     ///
     /// # Arguments
     ///
-    /// * `input`: A slice containing elements to be stored in the `SignVec`.
+    /// * `f`: A closure computing the new value of an element from the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element becomes a NaN and the policy is `NanPolicy::Reject`. As with
+    /// `update_all`, the elements that became NaNs keep their old values and the others
+    /// replaced before the panic stay filed under their new signs.
     ///
     /// # Examples
     ///
     /// ```
-    /// use signvec::{SignVec, svec, Sign};
+    /// use signvec::{Sign, SignVec, svec};
     ///
-    /// let mut sign_vec = SignVec::new();
-    /// assert_eq!(sign_vec.len(), 0);
-    /// assert_eq!(sign_vec.count(Sign::Plus), 0);
-    /// assert_eq!(sign_vec.count(Sign::Minus), 0);
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// sign_vec.map_in_place(|x| x - 10);
     ///
-    /// let input_slice = &[5, -10, 15];
-    /// sign_vec.extend(input_slice);
+    /// assert_eq!(sign_vec, svec![-5, -20, 5]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn map_in_place<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> T,
+    {
+        self.update_all(|_, x| *x = f(x));
+    }
+
+    /// Changes the element at `idx` in place through `f`, and moves its index to another set
+    /// only if its sign changed.
     ///
-    /// assert_eq!(sign_vec.len(), 3);
-    /// assert_eq!(sign_vec.count(Sign::Plus), 2);
-    /// assert_eq!(sign_vec.count(Sign::Minus), 1);
+    /// Unlike `set(idx, v[idx] * factor)`, this changes the element in place, and its index
+    /// moves only if its sign changed. The element is cloned only under `NanPolicy::Reject`, to
+    /// restore it should it become a NaN. Inside the hysteresis band the element keeps its side,
+    /// as with `set`.
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: The index of the element to change.
+    /// * `f`: A closure changing the element through a mutable reference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds, or if the element becomes a NaN and the policy is
+    /// `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5.0, -10.0, 15.0];
+    /// sign_vec.modify(1, |x| *x *= -0.5);
+    ///
+    /// assert_eq!(sign_vec, svec![5.0, 5.0, 15.0]);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 3);
     /// ```
     #[inline(always)]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn modify<F>(&mut self, idx: usize, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        let len = self.vals.len();
+        if idx >= len {
+            panic!("Index out of bounds: index {} length {}", idx, len);
+        }
+        // SAFETY: `idx` is in bounds, and the guard is dropped before this call returns, so it
+        // cannot be leaked with its index still filed under the old sign.
+        let mut val = unsafe { SignVecMut::new(self, idx, false) };
+        f(&mut val);
+    }
+
+    /// Returns the `NanPolicy` applied by this `SignVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, SignVec};
+    ///
+    /// let sign_vec = SignVec::<f64>::new();
+    ///
+    /// assert_eq!(sign_vec.nan_policy(), NanPolicy::Track);
+    /// ```
+    #[inline(always)]
+    pub fn nan_policy(&self) -> NanPolicy {
+        self.nan_policy
     }
     /// Removes and returns the last element from this `SignVec`, or `None` if it is empty.
     ///
@@ -763,14 +1485,7 @@ where
     pub fn pop(&mut self) -> Option<T> {
         if let Some(topop) = self.vals.pop() {
             let idx = self.vals.len(); // Get the new length after popping.
-            match topop.sign() {
-                Sign::Plus => {
-                    self.pos.remove(&idx);
-                }
-                Sign::Minus => {
-                    self.neg.remove(&idx);
-                }
-            };
+            bucket!(self, self.bucket_of(idx)).remove(&idx);
             Some(topop)
         } else {
             None
@@ -786,6 +1501,10 @@ where
     ///
     /// * `element`: The element to append.
     ///
+    /// # Panics
+    ///
+    /// Panics if the vector already holds as many elements as its index backend can address.
+    ///
     /// # Examples
     ///
     /// ```
@@ -798,11 +1517,11 @@ where
     /// ```
     #[inline(always)]
     pub fn push(&mut self, element: T) {
+        self.check_len(1).unwrap_or_else(|err| panic!("{}", err));
         let index = self.vals.len();
-        match element.sign() {
-            Sign::Plus => self.pos.insert(index),
-            Sign::Minus => self.neg.insert(index),
-        };
+        let bucket = self.rules().bucket(&element);
+        self.grow(1);
+        bucket!(self, bucket).insert(index);
         self.vals.push(element);
     }
 
@@ -812,6 +1531,9 @@ where
     /// this `SignVec`. It updates the `pos` and `neg` sets accordingly based on the sign of the
     /// removed element.
     ///
+    /// As with `insert`, the cost is that of shifting the elements after `index`; the default
+    /// `IndexSet` backend shifts its indices in O(√n) steps.
+    ///
     /// # Arguments
     ///
     /// * `index`: The index of the element to remove.
@@ -837,28 +1559,18 @@ where
     /// ```
     #[inline(always)]
     pub fn remove(&mut self, index: usize) -> T {
-        self.pos = self
-            .pos
-            .iter()
-            .map(|&idx| if idx > index { idx - 1 } else { idx })
-            .collect();
-        self.neg = self
-            .neg
-            .iter()
-            .map(|&idx| if idx > index { idx - 1 } else { idx })
-            .collect();
         let removed = self.vals.remove(index);
-        match removed.sign() {
-            Sign::Plus => self.pos.remove(&index),
-            Sign::Minus => self.neg.remove(&index),
-        };
+        self.pos.remove_shift(index);
+        self.neg.remove_shift(index);
+        self.zero.remove_shift(index);
+        self.nan.remove_shift(index);
         removed
     }
     /// Reserves capacity for at least `additional` more elements in `vals`.
     ///
     /// This method reserves capacity for at least `additional` more elements in the `vals` vector of
-    /// this `SignVec`. It also reserves capacity in the `pos` and `neg` sets accordingly based on the
-    /// new capacity of the `vals` vector.
+    /// this `SignVec`. It also reserves capacity in the `pos`, `neg` and `zero` sets accordingly based
+    /// on the new capacity of the `vals` vector.
     ///
     /// # Arguments
     ///
@@ -876,17 +1588,15 @@ where
     /// ```
     #[inline(always)]
     pub fn reserve(&mut self, additional: usize) {
-        let new_capacity = self.vals.len() + additional;
         self.vals.reserve(additional);
-        self.pos.reserve(new_capacity);
-        self.neg.reserve(new_capacity);
+        self.reserve_indices();
     }
 
     /// Reserves the exact capacity for `additional` more elements in `vals`.
     ///
     /// This method reserves the exact capacity for `additional` more elements in the `vals` vector of
-    /// this `SignVec`. It also reserves capacity in the `pos` and `neg` sets accordingly based on the
-    /// new capacity of the `vals` vector.
+    /// this `SignVec`. It also reserves capacity in the `pos`, `neg` and `zero` sets accordingly based
+    /// on the new capacity of the `vals` vector.
     ///
     /// # Arguments
     ///
@@ -904,10 +1614,8 @@ where
     /// ```
     #[inline(always)]
     pub fn reserve_exact(&mut self, additional: usize) {
-        let new_capacity = self.vals.len() + additional;
         self.vals.reserve_exact(additional);
-        self.pos.reserve(new_capacity);
-        self.neg.reserve(new_capacity);
+        self.reserve_indices();
     }
 
     /// Resizes the `SignVec` in place to a new length.
@@ -919,7 +1627,7 @@ where
     ///
     /// * `new_len`: The new length of the `SignVec`.
     /// * `value`: The value to initialize new elements with, if `new_len` is greater than the current
-    ///            length.
+    ///   length.
     ///
     /// # Examples
     ///
@@ -936,20 +1644,19 @@ where
         let old_len = self.vals.len();
         match new_len > old_len {
             true => {
-                self.vals.resize(new_len, value.clone());
-                match value.sign() {
-                    Sign::Plus => (old_len..new_len).for_each(|i| {
-                        self.pos.insert(i);
-                    }),
-                    Sign::Minus => (old_len..new_len).for_each(|i| {
-                        self.neg.insert(i);
-                    }),
-                };
+                let bucket = self.rules().bucket(&value);
+                self.grow(new_len - old_len);
+                self.vals.resize(new_len, value);
+                (old_len..new_len).for_each(|i| {
+                    bucket!(self, bucket).insert(i);
+                });
             }
             false => {
                 (new_len..old_len).for_each(|i| {
                     self.pos.remove(&i);
                     self.neg.remove(&i);
+                    self.zero.remove(&i);
+                    self.nan.remove(&i);
                 });
                 self.vals.truncate(new_len);
             }
@@ -985,12 +1692,11 @@ where
         let old_len = self.vals.len();
         match new_len > old_len {
             true => {
+                let rules = self.rules();
+                self.grow(new_len - old_len);
                 (old_len..new_len).for_each(|i| {
                     let value = f();
-                    match value.sign() {
-                        Sign::Plus => self.pos.insert(i),
-                        Sign::Minus => self.neg.insert(i),
-                    };
+                    bucket!(self, rules.bucket(&value)).insert(i);
                     self.vals.push(value);
                 });
             }
@@ -998,6 +1704,8 @@ where
                 (new_len..old_len).for_each(|i| {
                     self.pos.remove(&i);
                     self.neg.remove(&i);
+                    self.zero.remove(&i);
+                    self.nan.remove(&i);
                 });
                 self.vals.truncate(new_len);
             }
@@ -1009,10 +1717,13 @@ where
     /// this `SignVec`. It also adjusts the `pos` and `neg` sets accordingly based on the retained
     /// elements.
     ///
+    /// This is done in a single pass that renumbers the index sets as the elements are compacted.
+    /// The retained elements keep their buckets and are not classified again.
+    ///
     /// # Arguments
     ///
     /// * `f`: A closure that takes a reference to an element and returns `true` if the element should
-    ///        be retained, or `false` otherwise.
+    ///   be retained, or `false` otherwise.
     ///
     /// # Examples
     ///
@@ -1025,12 +1736,12 @@ where
     /// assert_eq!(sign_vec, svec![5, 15]);
     /// ```
     #[inline(always)]
-    pub fn retain<F>(&mut self, f: F)
+    pub fn retain<F>(&mut self, mut f: F)
     where
         F: FnMut(&T) -> bool,
     {
-        self.vals.retain(f);
-        self.sync();
+        let len = self.vals.len();
+        self.extract_with(0, len, |x| !f(x), false).for_each(drop);
     }
 
     /// Retains only the elements specified by the mutable predicate `f`.
@@ -1039,10 +1750,13 @@ where
     /// vector of this `SignVec`. It also adjusts the `pos` and `neg` sets accordingly based on the
     /// retained elements.
     ///
+    /// Like `retain`, this is a single pass. Since `f` may change the elements it keeps, those
+    /// are classified again.
+    ///
     /// # Arguments
     ///
     /// * `f`: A closure that takes a mutable reference to an element and returns `true` if the
-    ///        element should be retained, or `false` otherwise.
+    ///   element should be retained, or `false` otherwise.
     ///
     /// # Examples
     ///
@@ -1055,18 +1769,18 @@ where
     /// assert_eq!(sign_vec, svec![5, 15]);
     /// ```
     #[inline(always)]
-    pub fn retain_mut<F>(&mut self, f: F)
+    pub fn retain_mut<F>(&mut self, mut f: F)
     where
         F: FnMut(&mut T) -> bool,
     {
-        self.vals.retain_mut(f);
-        self.sync();
+        let len = self.vals.len();
+        self.extract_with(0, len, |x| !f(x), true).for_each(drop);
     }
 
     /// Returns a random index of an element with the specified sign.
     ///
-    /// This method returns a random index of an element with the specified sign (`Sign::Plus` or
-    /// `Sign::Minus`) in the `SignVec`. If no elements with the specified sign exist, `None` is
+    /// This method returns a random index of an element with the specified sign (`Sign::Plus`,
+    /// `Sign::Minus` or `Sign::Zero`) in the `SignVec`. If no elements with the specified sign exist, `None` is
     /// returned.
     ///
     /// # Arguments
@@ -1091,6 +1805,7 @@ where
         match sign {
             Sign::Plus => self.pos.random(rng),
             Sign::Minus => self.neg.random(rng),
+            Sign::Zero => self.zero.random(rng),
         }
     }
 
@@ -1118,8 +1833,6 @@ where
     ///
     /// assert_eq!(sv[idx], 10); // Assumes that `svec!` macro creates a vector where the index of 10 is accessible.
     /// ```
-    ///
-
     #[inline(always)]
     pub fn random_pos(&self, rng: &mut WyRand) -> Option<usize> {
         self.pos.random(rng)
@@ -1152,6 +1865,94 @@ where
         self.neg.random(rng)
     }
 
+    /// Returns a random index of an element equal to zero.
+    ///
+    /// This method is a specializion of the `random` function, for situations
+    /// where the desired sign (zero, in this case) is known at compile time.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng`: A mutable reference to a random number generator implementing the `WyRand` trait.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    /// use nanorand::WyRand;
+    ///
+    /// let sv = svec![5, 0, 15];
+    /// let mut rng = WyRand::new();
+    /// let idx = sv.random_zero(&mut rng).unwrap();
+    ///
+    /// assert_eq!(sv[idx], 0);
+    /// ```
+    #[inline(always)]
+    pub fn random_zero(&self, rng: &mut WyRand) -> Option<usize> {
+        self.zero.random(rng)
+    }
+
+    /// Returns a random index of a NaN element tracked under `NanPolicy::Track`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng`: A mutable reference to a random number generator implementing the `WyRand` trait.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    /// use nanorand::WyRand;
+    ///
+    /// let sv = svec![5.0, f64::NAN, 15.0];
+    /// let mut rng = WyRand::new();
+    ///
+    /// assert_eq!(sv.random_nan(&mut rng), Some(1));
+    /// ```
+    #[inline(always)]
+    pub fn random_nan(&self, rng: &mut WyRand) -> Option<usize> {
+        self.nan.random(rng)
+    }
+
+    /// Returns mutable references to the elements and to the index sets, the latter in the
+    /// order `pos`, `neg`, `zero`, `nan`.
+    ///
+    /// This is an escape hatch for updates the other methods cannot express. Writing through
+    /// the returned references bypasses every check, so whatever the caller changes it must put
+    /// back in order before the `SignVec` is used again, or call `sync` to rebuild the sets.
+    ///
+    /// # Safety
+    ///
+    /// Once the references are dropped, every index below the length of `vals` must be in
+    /// exactly one set, no set may hold an index at or past that length, and each element must
+    /// be filed as the vector's classifier, `NanPolicy` and hysteresis band allow. Methods of a
+    /// `SignVec` whose sets break these rules may panic or return wrong results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// unsafe {
+    ///     let (vals, [pos, neg, _, _]) = sign_vec.raw_parts_mut();
+    ///     vals.swap(0, 1);
+    ///     pos.remove(&0);
+    ///     neg.remove(&1);
+    ///     pos.insert(1);
+    ///     neg.insert(0);
+    /// }
+    ///
+    /// assert_eq!(sign_vec, svec![-10, 5, 15]);
+    /// assert!(sign_vec.indices(Sign::Minus).contains(&0));
+    /// ```
+    #[inline(always)]
+    pub unsafe fn raw_parts_mut(&mut self) -> (&mut Vec<T>, [&mut S; 4]) {
+        (
+            &mut self.vals,
+            [&mut self.pos, &mut self.neg, &mut self.zero, &mut self.nan],
+        )
+    }
+
     /// Sets the length of the vector.
     ///
     /// This method sets the length of the vector to `new_len`. If `new_len` is greater than the current
@@ -1194,16 +1995,10 @@ where
                 // SAFETY: The caller must ensure that the elements at old_len..new_len are properly initialized.
                 self.vals.set_len(new_len);
                 let vals_ptr = self.vals.as_mut_ptr();
+                let rules = self.rules();
                 (old_len..new_len).for_each(|i| {
                     // SAFETY: This dereference is safe under the assumption that elements at old_len..new_len are initialized.
-                    match unsafe { &*vals_ptr.add(i) }.sign() {
-                        Sign::Plus => {
-                            self.pos.insert(i);
-                        }
-                        Sign::Minus => {
-                            self.neg.insert(i);
-                        }
-                    }
+                    bucket!(self, rules.bucket(unsafe { &*vals_ptr.add(i) })).insert(i);
                 });
             }
             std::cmp::Ordering::Less => {
@@ -1211,6 +2006,8 @@ where
                 (new_len..old_len).for_each(|i| {
                     self.pos.remove(&i);
                     self.neg.remove(&i);
+                    self.zero.remove(&i);
+                    self.nan.remove(&i);
                 });
                 // SAFETY: This is safe as we're only reducing the vector's length, not accessing any elements.
                 self.vals.set_len(new_len);
@@ -1224,8 +2021,8 @@ where
     /// Sets the value at the specified index.
     ///
     /// This method sets the value at the specified index to the given value. It also updates the
-    /// positive (`pos`) and negative (`neg`) sets accordingly based on the sign change of the new
-    /// value compared to the old value.
+    /// positive (`pos`), negative (`neg`) and `zero` sets accordingly based on the sign change of
+    /// the new value compared to the old value.
     ///
     /// # Arguments
     ///
@@ -1267,6 +2064,9 @@ where
     /// bounds checking. It also updates the positive (`pos`) and negative (`neg`) sets accordingly
     /// based on the sign change of the new value compared to the old value.
     ///
+    /// If a hysteresis band is set and the new value lies inside it, the element stays in the set
+    /// it was in.
+    ///
     /// # Safety
     ///
     /// The caller must ensure that `idx` is within the bounds of the vector. Failure to do so may
@@ -1287,28 +2087,264 @@ where
     /// ```
     #[inline(always)]
     pub unsafe fn set_unchecked(&mut self, idx: usize, mut val: T) {
+        let old_bucket = self.bucket_of(idx);
+        let new_bucket = self.next_bucket(old_bucket, &val);
         let old_val = &mut *self.vals.as_mut_ptr().add(idx);
-        let old_sign = old_val.sign();
-        let new_sign = val.sign();
         std::mem::swap(old_val, &mut val);
-        if old_sign != new_sign {
-            match new_sign {
-                Sign::Plus => {
-                    self.neg.remove(&idx);
-                    self.pos.insert(idx);
+        if old_bucket != new_bucket {
+            bucket!(self, old_bucket).remove(&idx);
+            bucket!(self, new_bucket).insert(idx);
+        }
+    }
+
+    /// Sets many elements at once, returning the number of elements that changed sign.
+    ///
+    /// Every index and every value is checked before anything is written, so a failed check
+    /// leaves the vector untouched. The values are then written in index order, while the
+    /// indices whose sign changed are collected per set and moved set by set at the end. An
+    /// index given twice ends up with its last value, and each of its changes counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `updates`: The `(index, value)` pairs to write.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds, or if a value is a NaN and the policy is
+    /// `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15, -20];
+    /// let flips = sign_vec.set_many([(0, 6), (1, 10), (3, 0)]);
+    ///
+    /// assert_eq!(flips, 2);
+    /// assert_eq!(sign_vec, svec![6, 10, 15, 0]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 0);
+    /// ```
+    pub fn set_many<I>(&mut self, updates: I) -> usize
+    where
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let mut updates: Vec<(usize, T)> = updates.into_iter().collect();
+        let len = self.vals.len();
+        for (idx, val) in &updates {
+            if *idx >= len {
+                panic!("Index out of bounds: index {} length {}", idx, len);
+            }
+            if let Err(err) = self.admit(*idx, val) {
+                panic!("{}", err);
+            }
+        }
+        // The sort is stable, so the updates of one index stay in order and come together.
+        updates.sort_by_key(|&(idx, _)| idx);
+        let mut removed: [Vec<usize>; 4] = Default::default();
+        let mut inserted: [Vec<usize>; 4] = Default::default();
+        let mut flips = 0;
+        let mut updates = updates.into_iter().peekable();
+        while let Some((idx, mut val)) = updates.next() {
+            // The sets are not touched until the end, so they still hold the old bucket.
+            let old = self.bucket_of(idx);
+            let mut new = self.next_bucket(old, &val);
+            flips += (new != old) as usize;
+            while let Some((_, next)) = updates.next_if(|&(i, _)| i == idx) {
+                let bucket = self.next_bucket(new, &next);
+                flips += (bucket != new) as usize;
+                (new, val) = (bucket, next);
+            }
+            self.vals[idx] = val;
+            if old != new {
+                removed[old as usize].push(idx);
+                inserted[new as usize].push(idx);
+            }
+        }
+        for bucket in [Bucket::Plus, Bucket::Minus, Bucket::Zero, Bucket::NaN] {
+            let set = bucket!(self, bucket);
+            for idx in &removed[bucket as usize] {
+                set.remove(idx);
+            }
+            for &idx in &inserted[bucket as usize] {
+                set.insert(idx);
+            }
+        }
+        flips
+    }
+
+    /// Overwrites the elements from `start` on with `vals`, returning the number of elements
+    /// that changed sign.
+    ///
+    /// The range and the values are checked before anything is written. Without a hysteresis
+    /// band the elements are classified 64 at a time before and after the write, and only
+    /// those whose sign changed move between sets.
+    ///
+    /// # Arguments
+    ///
+    /// * `start`: The index of the first element to overwrite.
+    /// * `vals`: The new values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start + vals.len()` exceeds the length, or if a value is a NaN and the policy
+    /// is `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15, -20];
+    /// let flips = sign_vec.set_range(1, &[-1, -2, 3]);
+    ///
+    /// assert_eq!(flips, 2);
+    /// assert_eq!(sign_vec, svec![5, -1, -2, 3]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    pub fn set_range(&mut self, start: usize, vals: &[T]) -> usize {
+        if start > self.vals.len() || vals.len() > self.vals.len() - start {
+            panic!("Invalid range for set_range");
+        }
+        for (offset, val) in vals.iter().enumerate() {
+            if let Err(err) = self.admit(start + offset, val) {
+                panic!("{}", err);
+            }
+        }
+        let mut flips = 0;
+        if self.dead_band.is_some() {
+            for (offset, val) in vals.iter().enumerate() {
+                if self.replace_at(start + offset, val.clone()) {
+                    flips += 1;
                 }
-                Sign::Minus => {
-                    self.pos.remove(&idx);
-                    self.neg.insert(idx);
+            }
+            return flips;
+        }
+        let rules = self.rules();
+        for (offset, chunk) in vals.chunks(64).enumerate() {
+            let base = start + offset * 64;
+            let end = base + chunk.len();
+            let dst = &mut self.vals[base..end];
+            let before = rules.masks(dst);
+            dst.clone_from_slice(chunk);
+            flips += self.refile_chunk(base, end, before);
+        }
+        flips
+    }
+
+    /// Sets or clears the hysteresis band.
+    ///
+    /// With `Some(eps)`, an element updated through `set` or `set_unchecked` to a value strictly
+    /// between `-eps` and `+eps` stays in the set it was in, and only changes sides once it
+    /// reaches `+eps` or `-eps`. Newly added elements are classified as usual, and so is every
+    /// element on `sync`. The band is relative to zero, even with a custom classifier.
+    ///
+    /// Enabling the band keeps the current classification. Clearing it with `None`
+    /// reclassifies every element.
+    ///
+    /// # Arguments
+    ///
+    /// * `eps`: The half-width of the band, or `None` to disable hysteresis.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![1.0, -1.0];
+    /// sign_vec.set_hysteresis(Some(0.5));
+    ///
+    /// sign_vec.set(0, -0.2); // inside the band, still positive
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    ///
+    /// sign_vec.set(0, -0.5); // crossed -eps
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    ///
+    /// sign_vec.set_hysteresis(None);
+    /// sign_vec.set(1, 0.2);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    /// ```
+    pub fn set_hysteresis(&mut self, eps: Option<T>)
+    where
+        T: PartialOrd + Neg<Output = T>,
+    {
+        match eps {
+            Some(eps) => {
+                self.dead_band = Some(DeadBand {
+                    low: -eps.clone(),
+                    high: eps,
+                    contains: band_contains::<T>,
+                });
+            }
+            None => {
+                if self.dead_band.take().is_some() {
+                    self.sync();
                 }
             }
         }
     }
+
+    /// Replaces the function classifying the non-NaN elements, reclassifying every element.
+    ///
+    /// # Arguments
+    ///
+    /// * `classifier`: The function deciding the sign of each element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![0.2, 0.7, -1.0];
+    /// sign_vec.set_classifier(|x| if *x > 0.5 { Sign::Plus } else { Sign::Minus });
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    pub fn set_classifier(&mut self, classifier: fn(&T) -> Sign) {
+        self.classifier = Some(classifier);
+        self.sync();
+    }
+
+    /// Changes the `NanPolicy` of this `SignVec`, reclassifying its elements.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SignVecError::NanRejected` for the first NaN found if `policy` is
+    /// `NanPolicy::Reject` and the vector holds NaNs. The vector is left unchanged in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, Sign, SignVec, SignVecError, svec};
+    ///
+    /// let mut sign_vec = svec![1.0, -f64::NAN];
+    /// assert_eq!(sign_vec.count_nan(), 1);
+    ///
+    /// sign_vec.set_nan_policy(NanPolicy::SignBit).unwrap();
+    /// assert_eq!(sign_vec.count(Sign::Minus), 1);
+    /// assert_eq!(sign_vec.count_nan(), 0);
+    ///
+    /// assert_eq!(
+    ///     sign_vec.set_nan_policy(NanPolicy::Reject),
+    ///     Err(SignVecError::NanRejected { index: 1 })
+    /// );
+    /// ```
+    pub fn set_nan_policy(&mut self, policy: NanPolicy) -> Result<(), SignVecError> {
+        if policy == NanPolicy::Reject {
+            if let Some(index) = self.vals.iter().position(|v| v.is_nan()) {
+                return Err(SignVecError::NanRejected { index });
+            }
+        }
+        self.nan_policy = policy;
+        self.sync();
+        Ok(())
+    }
     /// Shrinks the capacity of the vector to at least `min_capacity`.
     ///
     /// This method reduces the capacity of the vector to at least `min_capacity` while maintaining
     /// its length. If the current capacity is already less than or equal to `min_capacity`, this
-    /// method does nothing.
+    /// method does nothing. The index sets shrink alike, keeping room for every element.
     ///
     /// # Arguments
     ///
@@ -1329,14 +2365,18 @@ where
     #[inline(always)]
     pub fn shrink_to(&mut self, min_capacity: usize) {
         self.vals.shrink_to(min_capacity);
+        let min_capacity = min_capacity.max(self.vals.len());
         self.pos.shrink_to(min_capacity);
         self.neg.shrink_to(min_capacity);
+        self.zero.shrink_to(min_capacity);
+        self.nan.shrink_to(min_capacity);
     }
 
     /// Shrinks the capacity of the vector to fit its current length.
     ///
     /// This method reduces the capacity of the vector to fit its current length. If the current
-    /// capacity is already equal to the length of the vector, this method does nothing.
+    /// capacity is already equal to the length of the vector, this method does nothing. The index
+    /// sets shrink alike, keeping room for every element.
     ///
     /// # Examples
     ///
@@ -1352,8 +2392,10 @@ where
     #[inline(always)]
     pub fn shrink_to_fit(&mut self) {
         self.vals.shrink_to_fit();
-        self.pos.shrink_to_fit();
-        self.neg.shrink_to_fit();
+        self.pos.shrink_to(self.vals.len());
+        self.neg.shrink_to(self.vals.len());
+        self.zero.shrink_to(self.vals.len());
+        self.nan.shrink_to(self.vals.len());
     }
 
     /// Returns a mutable slice of the unused capacity of the vector.
@@ -1418,7 +2460,7 @@ where
     /// assert_eq!(sign_vec, svec![5, -10]);
     /// assert_eq!(new_vec, svec![15, -20]);
     /// ```
-    pub fn split_off(&mut self, at: usize) -> SignVec<T> {
+    pub fn split_off(&mut self, at: usize) -> SignVec<T, S> {
         // Ensure 'at' is within bounds to prevent out-of-bounds access.
         if at > self.vals.len() {
             panic!(
@@ -1428,23 +2470,24 @@ where
             );
         }
         let new_vals = self.vals.split_off(at);
-        let mut new_pos = Set::with_max(new_vals.len());
-        let mut new_neg = Set::with_max(new_vals.len());
-        (0..new_vals.len()).for_each(|i| {
-            if self.pos.contains(&(at + i)) {
-                self.pos.remove(&(at + i));
-                new_pos.insert(i);
-            } else if self.neg.remove(&(at + i)) {
-                // This also acts as a check, removing the item if present.
-                new_neg.insert(i);
-            }
-        });
-        SignVec {
+        let max = new_vals.len();
+        let mut other = SignVec {
             vals: new_vals,
-            pos: new_pos,
-            neg: new_neg,
+            pos: S::with_max(max),
+            neg: S::with_max(max),
+            zero: S::with_max(max),
+            nan: S::with_max(max),
+            nan_policy: self.nan_policy,
+            classifier: self.classifier,
+            dead_band: self.dead_band.clone(),
             _marker: PhantomData,
-        }
+        };
+        (0..other.vals.len()).for_each(|i| {
+            let bucket = self.bucket_of(at + i);
+            bucket!(self, bucket).remove(&(at + i));
+            bucket!(other, bucket).insert(i);
+        });
+        other
     }
     /// Removes and returns the element at the specified index, replacing it with the last element.
     ///
@@ -1477,32 +2520,21 @@ where
     #[inline(always)]
     pub fn swap_remove(&mut self, index: usize) -> T {
         let removed_element = self.vals.swap_remove(index);
-        let sign = removed_element.sign();
-        match sign {
-            Sign::Plus => self.pos.remove(&index),
-            Sign::Minus => self.neg.remove(&index),
-        };
+        bucket!(self, self.bucket_of(index)).remove(&index);
 
-        if index < self.vals.len() {
-            let swapped_element_sign = self.vals[index].sign();
-            match swapped_element_sign {
-                Sign::Plus => {
-                    self.pos.remove(&self.vals.len());
-                    self.pos.insert(index);
-                }
-                Sign::Minus => {
-                    self.neg.remove(&self.vals.len());
-                    self.neg.insert(index);
-                }
-            }
+        let last = self.vals.len();
+        if index < last {
+            let swapped = bucket!(self, self.bucket_of(last));
+            swapped.remove(&last);
+            swapped.insert(index);
         }
         removed_element
     }
 
-    /// Synchronizes the positive and negative sets with the vector's elements.
+    /// Synchronizes the positive, negative and zero sets with the vector's elements.
     ///
-    /// This method clears the positive (`pos`) and negative (`neg`) sets, and then re-inserts the
-    /// indices of the elements in the vector according to their signs.
+    /// This method clears the positive (`pos`), negative (`neg`) and `zero` sets, and then
+    /// re-inserts the indices of the elements in the vector according to their signs.
     ///
     /// # Examples
     ///
@@ -1519,12 +2551,13 @@ where
     pub fn sync(&mut self) {
         self.pos.clear();
         self.neg.clear();
-        self.vals.iter().enumerate().for_each(|(idx, val)| {
-            match val.sign() {
-                Sign::Plus => self.pos.insert(idx),
-                Sign::Minus => self.neg.insert(idx),
-            };
-        });
+        self.zero.clear();
+        self.nan.clear();
+        let rules = self.rules();
+        for (i, chunk) in self.vals.chunks(64).enumerate() {
+            let sets = [&mut self.pos, &mut self.neg, &mut self.zero, &mut self.nan];
+            rules.file(chunk, i * 64, sets);
+        }
     }
     /// Truncates the `SignVec` to the specified length.
     ///
@@ -1549,20 +2582,121 @@ where
     pub fn truncate(&mut self, len: usize) {
         if len < self.vals.len() {
             let old_len = self.vals.len();
-            unsafe {
-                let vals_ptr = self.vals.as_ptr();
-                for i in len..old_len {
-                    let val = &*vals_ptr.add(i);
-                    match val.sign() {
-                        Sign::Plus => self.pos.remove(&i),
-                        Sign::Minus => self.neg.remove(&i),
-                    };
-                }
+            for i in len..old_len {
+                bucket!(self, self.bucket_of(i)).remove(&i);
             }
             self.vals.truncate(len);
         }
     }
 
+    /// Appends each element of a slice, unless one of them is rejected by the `NanPolicy`.
+    ///
+    /// Unlike `extend_from_slice`, this method checks every element before appending any of
+    /// them, so the vector is left untouched when an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SignVecError::NanRejected` for the first NaN in `other` if the policy is
+    /// `NanPolicy::Reject`, and `SignVecError::LengthLimitExceeded` if the index backend cannot
+    /// address the extended vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, SignVec, SignVecError};
+    ///
+    /// let mut sign_vec = SignVec::with_nan_policy(NanPolicy::Reject);
+    /// sign_vec.try_extend_from_slice(&[1.0, -2.0]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     sign_vec.try_extend_from_slice(&[3.0, f64::NAN]),
+    ///     Err(SignVecError::NanRejected { index: 3 })
+    /// );
+    /// assert_eq!(sign_vec.len(), 2);
+    /// ```
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), SignVecError> {
+        self.check_len(other.len())?;
+        if self.nan_policy == NanPolicy::Reject {
+            if let Some(i) = other.iter().position(|v| v.is_nan()) {
+                return Err(SignVecError::NanRejected {
+                    index: self.vals.len() + i,
+                });
+            }
+        }
+        self.extend_from_slice(other);
+        Ok(())
+    }
+
+    /// Appends an element, unless it is rejected by the `NanPolicy`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SignVecError::NanRejected` if `element` is a NaN and the policy is
+    /// `NanPolicy::Reject`, and `SignVecError::LengthLimitExceeded` if the vector already holds
+    /// as many elements as its index backend can address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, SignVec, SignVecError};
+    ///
+    /// let mut sign_vec = SignVec::with_nan_policy(NanPolicy::Reject);
+    /// assert!(sign_vec.try_push(1.0).is_ok());
+    /// assert_eq!(
+    ///     sign_vec.try_push(f64::NAN),
+    ///     Err(SignVecError::NanRejected { index: 1 })
+    /// );
+    /// assert_eq!(sign_vec.len(), 1);
+    /// ```
+    pub fn try_push(&mut self, element: T) -> Result<(), SignVecError> {
+        self.check_len(1)?;
+        self.admit(self.vals.len(), &element)?;
+        self.push(element);
+        Ok(())
+    }
+
+    /// Sets the value at the specified index, unless the index is out of bounds or the value is
+    /// rejected by the `NanPolicy`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SignVecError::IndexOutOfBounds` if `idx` is out of bounds, and
+    /// `SignVecError::NanRejected` if `val` is a NaN and the policy is `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, SignVec, SignVecError};
+    ///
+    /// let mut sign_vec = SignVec::with_nan_policy(NanPolicy::Reject);
+    /// sign_vec.push(1.0);
+    ///
+    /// assert!(sign_vec.try_set(0, -1.0).is_ok());
+    /// assert_eq!(
+    ///     sign_vec.try_set(0, f64::NAN),
+    ///     Err(SignVecError::NanRejected { index: 0 })
+    /// );
+    /// assert_eq!(
+    ///     sign_vec.try_set(3, 1.0),
+    ///     Err(SignVecError::IndexOutOfBounds { index: 3, len: 1 })
+    /// );
+    /// assert_eq!(sign_vec[0], -1.0);
+    /// ```
+    pub fn try_set(&mut self, idx: usize, val: T) -> Result<(), SignVecError> {
+        if idx >= self.vals.len() {
+            return Err(SignVecError::IndexOutOfBounds {
+                index: idx,
+                len: self.vals.len(),
+            });
+        }
+        self.admit(idx, &val)?;
+        // Safety: We've verified that idx is within bounds above
+        unsafe {
+            self.set_unchecked(idx, val);
+        }
+        Ok(())
+    }
+
     /// Tries to reserve capacity for at least `additional` more elements to be inserted in the vector.
     ///
     /// This method tries to reserve capacity for at least `additional` more elements to be inserted
@@ -1590,8 +2724,7 @@ where
         additional: usize,
     ) -> Result<(), std::collections::TryReserveError> {
         self.vals.try_reserve(additional)?;
-        self.pos.reserve(self.vals.len() + additional);
-        self.neg.reserve(self.vals.len() + additional);
+        self.reserve_indices();
         Ok(())
     }
 
@@ -1622,11 +2755,110 @@ where
         additional: usize,
     ) -> Result<(), std::collections::TryReserveError> {
         self.vals.try_reserve_exact(additional)?;
-        self.pos.reserve(self.vals.len() + additional);
-        self.neg.reserve(self.vals.len() + additional);
+        self.reserve_indices();
         Ok(())
     }
 
+    /// Calls `f` on every element with its index, filing each element under its new sign right
+    /// after the call.
+    ///
+    /// The update and the index sets are kept in one pass: the elements are classified 64 at a
+    /// time before and after `f` runs on them, and only those whose sign changed move between
+    /// sets. Inside the hysteresis band elements keep their side, as with `set`; with a band
+    /// set, the elements are updated one guard at a time instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `f`: A closure taking the index of an element and a mutable reference to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element becomes a NaN and the policy is `NanPolicy::Reject`. The elements
+    /// that became NaNs keep their old values, and those updated before the panic stay updated
+    /// and filed under their new signs, as they do should `f` panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![1, 2, 3, 4];
+    /// sign_vec.update_all(|i, x| {
+    ///     if i % 2 == 1 {
+    ///         *x = -*x;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(sign_vec, svec![1, -2, 3, -4]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn update_all<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut T),
+    {
+        if self.dead_band.is_some() {
+            for idx in 0..self.vals.len() {
+                // SAFETY: as in `modify`.
+                let mut val = unsafe { SignVecMut::new(self, idx, false) };
+                f(idx, &mut val);
+            }
+            return;
+        }
+        // Without a band every element is filed under its own classification, so comparing the
+        // masks of a chunk before and after the update singles out the elements to move.
+        let len = self.vals.len();
+        let mut chunk = ChunkUpdate {
+            saved: Vec::new(),
+            sign_vec: self,
+            base: 0,
+            end: 0,
+            before: SignMasks::default(),
+        };
+        for base in (0..len).step_by(64) {
+            chunk.open(base, len.min(base + 64));
+            let vals = &mut chunk.sign_vec.vals[base..chunk.end];
+            for (i, val) in vals.iter_mut().enumerate() {
+                f(base + i, val);
+            }
+            if let Some(index) = chunk.close() {
+                panic!("{}", SignVecError::NanRejected { index });
+            }
+        }
+    }
+
+    /// Replaces the element at `idx` with `f` applied to it, moving its index to another set
+    /// only if its sign changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: The index of the element to replace.
+    /// * `f`: A closure computing the new value from the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds, or if the new value is a NaN and the policy is
+    /// `NanPolicy::Reject`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec, svec};
+    ///
+    /// let mut sign_vec = svec![5, -10, 15];
+    /// sign_vec.update_with(0, |old| old - 10);
+    ///
+    /// assert_eq!(sign_vec, svec![-5, -10, 15]);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn update_with<F>(&mut self, idx: usize, f: F)
+    where
+        F: FnOnce(&T) -> T,
+    {
+        self.modify(idx, |x| *x = f(x));
+    }
+
     /// Returns an iterator over the values with the specified sign.
     ///
     /// This method returns an iterator over the values in the `SignVec` with the specified `sign`.
@@ -1646,134 +2878,1151 @@ where
     /// assert_eq!(positive_values, vec![&5, &15]);
     /// ```
     #[inline(always)]
-    pub fn values(&self, sign: Sign) -> SignVecValues<T> {
+    pub fn values(&self, sign: Sign) -> SignVecValues<'_, T, S> {
         SignVecValues::new(self, sign)
     }
 
-    /// Creates a new empty `SignVec` with the specified capacity.
-    ///
-    /// This method creates a new empty `SignVec` with the specified `capacity`.
-    ///
-    /// # Arguments
-    ///
-    /// * `capacity`: The capacity of the new `SignVec`.
-    ///
-    /// # Examples
+    /// Returns an iterator over the NaN values tracked under `NanPolicy::Track`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec};
+    ///
+    /// let sign_vec = svec![5.0, f64::NAN, 15.0];
+    ///
+    /// assert!(sign_vec.values_nan().all(|v| v.is_nan()));
+    /// assert_eq!(sign_vec.values_nan().count(), 1);
+    /// ```
+    #[inline(always)]
+    pub fn values_nan(&self) -> SignVecValues<'_, T, S> {
+        SignVecValues::from_set(self, &self.nan)
+    }
+
+    /// Creates a new empty `SignVec` storing its index sets in the backend `S`, with room for
+    /// `capacity` elements.
+    ///
+    /// The other constructors use the default `IndexSet` backend. A `SignVec` with a different
+    /// backend starts from here, or from `collect`, and is configured with the `set_*` methods.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: The number of elements to reserve room for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{BitmapIndex, NanPolicy, SignVec};
+    ///
+    /// let mut sign_vec = SignVec::<f64, BitmapIndex>::with_backend(100);
+    /// sign_vec.set_nan_policy(NanPolicy::SignBit).unwrap();
+    /// sign_vec.push(-1.0);
+    /// assert!(sign_vec.capacity() >= 100);
+    /// ```
+    #[inline(always)]
+    pub fn with_backend(capacity: usize) -> Self {
+        Self {
+            vals: Vec::with_capacity(capacity),
+            pos: S::with_max(capacity),
+            neg: S::with_max(capacity),
+            zero: S::with_max(capacity),
+            nan: S::with_max(capacity),
+            nan_policy: NanPolicy::default(),
+            classifier: None,
+            dead_band: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> SignVec<T>
+where
+    T: Signable + Clone,
+{
+    /// Creates a new `SignVec` from a slice of elements.
+    ///
+    /// This method constructs a new `SignVec` by iterating over the elements in the input slice `input`.
+    /// It initializes the `vals` vector with the elements from the slice and populates the `pos` and
+    /// `neg` sets based on the sign of each element. The maximum index value is used to initialize the sets.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: A slice containing elements to be stored in the `SignVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{SignVec, svec, Sign};
+    ///
+    /// let mut sign_vec: SignVec<i32> = SignVec::new();
+    /// assert_eq!(sign_vec.len(), 0);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 0);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 0);
+    ///
+    /// let input_slice = &[5, -10, 15];
+    /// sign_vec.extend(input_slice);
+    ///
+    /// assert_eq!(sign_vec.len(), 3);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 2);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 1);
+    /// ```
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new empty `SignVec` with the specified capacity.
+    ///
+    /// This method creates a new empty `SignVec` with the specified `capacity`.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: The capacity of the new `SignVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::SignVec;
+    ///
+    /// let sign_vec: SignVec<f64> = SignVec::with_capacity(10);
+    /// assert!(sign_vec.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_backend(capacity)
+    }
+
+    /// Creates a new empty `SignVec` classifying its elements with `classifier` instead of
+    /// `Signable::sign`.
+    ///
+    /// This lets the same element type be split by different criteria, such as a threshold or
+    /// a struct field, without defining wrapper types. NaNs are still detected with
+    /// `Signable::is_nan` and handled by the `NanPolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `classifier`: The function deciding the sign of each element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec};
+    ///
+    /// let mut sign_vec = SignVec::with_classifier(|x: &f64| {
+    ///     if *x >= 100.0 {
+    ///         Sign::Plus
+    ///     } else {
+    ///         Sign::Minus
+    ///     }
+    /// });
+    /// sign_vec.extend_from_slice(&[50.0, 150.0, 99.9]);
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    /// assert_eq!(sign_vec.count(Sign::Minus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn with_classifier(classifier: fn(&T) -> Sign) -> Self {
+        Self {
+            classifier: Some(classifier),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a new empty `SignVec` with a hysteresis band of half-width `eps`.
+    ///
+    /// See `set_hysteresis` for how the band affects updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `eps`: The half-width of the band.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, SignVec};
+    ///
+    /// let mut sign_vec = SignVec::with_hysteresis(0.01);
+    /// sign_vec.push(1.0);
+    /// sign_vec.set(0, -0.001);
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Plus), 1);
+    /// ```
+    #[inline(always)]
+    pub fn with_hysteresis(eps: T) -> Self
+    where
+        T: PartialOrd + Neg<Output = T>,
+    {
+        let mut sign_vec = Self::default();
+        sign_vec.set_hysteresis(Some(eps));
+        sign_vec
+    }
+
+    /// Creates a new empty `SignVec` applying the given `NanPolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy`: The policy applied to NaN values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, Sign, SignVec};
+    ///
+    /// let mut sign_vec = SignVec::with_nan_policy(NanPolicy::SignBit);
+    /// sign_vec.push(-f64::NAN);
+    ///
+    /// assert_eq!(sign_vec.count(Sign::Minus), 1);
+    /// ```
+    #[inline(always)]
+    pub fn with_nan_policy(policy: NanPolicy) -> Self {
+        Self {
+            nan_policy: policy,
+            ..Self::default()
+        }
+    }
+}
+
+impl<T, S> SignVec<T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Returns a copy of the rules classifying the elements of this `SignVec`.
+    #[inline(always)]
+    pub(crate) fn rules(&self) -> Rules<T> {
+        Rules {
+            nan_policy: self.nan_policy,
+            classifier: self.classifier,
+        }
+    }
+
+    /// Builds a `SignVec` around `vals`, filing the elements 64 at a time with `sync`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is a NaN and the policy is `NanPolicy::Reject`.
+    #[inline(always)]
+    fn from_vals(vals: Vec<T>, nan_policy: NanPolicy) -> Self {
+        let mut sign_vec = Self::with_backend(0);
+        sign_vec.vals = vals;
+        sign_vec.nan_policy = nan_policy;
+        sign_vec.reserve_indices();
+        sign_vec.sync();
+        sign_vec
+    }
+
+    /// Returns the bucket for `val` replacing an element filed under `old`.
+    ///
+    /// Inside the dead band, the element keeps the side it was on.
+    #[inline(always)]
+    fn next_bucket(&self, old: Bucket, val: &T) -> Bucket {
+        match &self.dead_band {
+            Some(band) if old != Bucket::NaN && band.contains(val) => old,
+            _ => self.rules().bucket(val),
+        }
+    }
+
+    /// Returns the bucket the element at `idx` is currently filed under.
+    #[inline(always)]
+    fn bucket_of(&self, idx: usize) -> Bucket {
+        if self.pos.contains(&idx) {
+            Bucket::Plus
+        } else if self.neg.contains(&idx) {
+            Bucket::Minus
+        } else if self.zero.contains(&idx) {
+            Bucket::Zero
+        } else {
+            Bucket::NaN
+        }
+    }
+
+    /// Makes room for `additional` more elements, growing the index sets along with `vals`.
+    #[inline(always)]
+    fn grow(&mut self, additional: usize) {
+        if additional > self.vals.capacity() - self.vals.len() {
+            self.vals.reserve(additional);
+            self.reserve_indices();
+        }
+    }
+
+    /// Grows the index sets to address every slot `vals` has room for.
+    #[inline(always)]
+    pub(crate) fn reserve_indices(&mut self) {
+        let max_index = self.vals.capacity().saturating_sub(1);
+        self.pos.reserve(max_index);
+        self.neg.reserve(max_index);
+        self.zero.reserve(max_index);
+        self.nan.reserve(max_index);
+    }
+
+    /// Moves the elements of the chunk `base..end`, at most 64 long, whose sign changed since
+    /// the chunk was classified into `before`, returning how many moved.
+    ///
+    /// Only valid without a hysteresis band. NaNs are always looked at, since
+    /// `NanPolicy::SignBit` files them by a bit the masks ignore.
+    #[inline(always)]
+    fn refile_chunk(&mut self, base: usize, end: usize, before: SignMasks) -> usize {
+        let rules = self.rules();
+        let after = rules.masks(&self.vals[base..end]);
+        let mut changed =
+            (before.plus ^ after.plus) | (before.minus ^ after.minus) | before.nan | after.nan;
+        let mut moved = 0;
+        while changed != 0 {
+            let idx = base + changed.trailing_zeros() as usize;
+            changed &= changed - 1;
+            let old = self.bucket_of(idx);
+            let new = rules.bucket(&self.vals[idx]);
+            if old != new {
+                bucket!(self, old).remove(&idx);
+                bucket!(self, new).insert(idx);
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Replaces the element at `idx`, which must be in bounds, returning whether it moved to
+    /// another set.
+    #[inline(always)]
+    fn replace_at(&mut self, idx: usize, val: T) -> bool {
+        let old = self.bucket_of(idx);
+        let new = self.next_bucket(old, &val);
+        self.vals[idx] = val;
+        if old != new {
+            bucket!(self, old).remove(&idx);
+            bucket!(self, new).insert(idx);
+        }
+        old != new
+    }
+
+    /// Checks that `additional` more elements stay within the length the backend can address.
+    #[inline(always)]
+    fn check_len(&self, additional: usize) -> Result<(), SignVecError> {
+        if additional > S::MAX_LEN - self.vals.len() {
+            Err(SignVecError::LengthLimitExceeded {
+                max_len: S::MAX_LEN,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks `val`, destined for `index`, against the `NanPolicy`.
+    #[inline(always)]
+    fn admit(&self, index: usize, val: &T) -> Result<(), SignVecError> {
+        if self.nan_policy == NanPolicy::Reject && val.is_nan() {
+            Err(SignVecError::NanRejected { index })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Starts a `SignVecExtractIf` over `start..end`, classifying the elements that stay again
+    /// if `reclassify` is set.
+    #[inline(always)]
+    fn extract_with<F>(
+        &mut self,
+        start: usize,
+        end: usize,
+        pred: F,
+        reclassify: bool,
+    ) -> SignVecExtractIf<'_, T, F, S>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let empty = SignVec {
+            vals: Vec::new(),
+            pos: S::default(),
+            neg: S::default(),
+            zero: S::default(),
+            nan: S::default(),
+            nan_policy: self.nan_policy,
+            classifier: self.classifier,
+            dead_band: self.dead_band.clone(),
+            _marker: PhantomData,
+        };
+        let mut taken = std::mem::replace(self, empty);
+        let old_len = taken.vals.len();
+        // SAFETY: the iterator tracks the live elements itself and restores the length when it
+        // is dropped. Should it be leaked, the elements are leaked rather than dropped twice.
+        unsafe { taken.vals.set_len(0) };
+        SignVecExtractIf {
+            sign_vec: self,
+            taken,
+            pred,
+            reclassify,
+            idx: start,
+            end,
+            del: 0,
+            old_len,
+        }
+    }
+}
+
+/// The chunk of at most 64 elements `update_all` is updating, filed under the signs of its
+/// elements when closed or dropped, so that a panicking closure leaves no element misfiled.
+struct ChunkUpdate<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// The `SignVec` being updated.
+    sign_vec: &'a mut SignVec<T, S>,
+    /// The index of the first element of the chunk.
+    base: usize,
+    /// One past the index of the last element of the chunk, or `base` once it is closed.
+    end: usize,
+    /// The classification of the chunk before the update.
+    before: SignMasks,
+    /// The elements of the chunk before the update, kept under `NanPolicy::Reject` only.
+    saved: Vec<T>,
+}
+
+impl<'a, T, S> ChunkUpdate<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Opens the chunk `base..end`.
+    #[inline(always)]
+    fn open(&mut self, base: usize, end: usize) {
+        let vals = &self.sign_vec.vals[base..end];
+        self.before = self.sign_vec.rules().masks(vals);
+        if self.sign_vec.nan_policy == NanPolicy::Reject {
+            self.saved.clear();
+            self.saved.extend_from_slice(vals);
+        }
+        self.base = base;
+        self.end = end;
+    }
+
+    /// Files the elements of the chunk under their new signs, restoring those rejected as NaNs,
+    /// and returns the index of the first one restored.
+    #[inline(always)]
+    fn close(&mut self) -> Option<usize> {
+        let (base, end) = (self.base, self.end);
+        self.end = base;
+        let mut rejected = None;
+        if self.sign_vec.nan_policy == NanPolicy::Reject {
+            let vals = &mut self.sign_vec.vals[base..end];
+            for (i, (val, old)) in vals.iter_mut().zip(&self.saved).enumerate() {
+                if val.is_nan() {
+                    *val = old.clone();
+                    rejected = rejected.or(Some(base + i));
+                }
+            }
+        }
+        self.sign_vec.refile_chunk(base, end, self.before);
+        rejected
+    }
+}
+
+impl<'a, T, S> Drop for ChunkUpdate<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Files the chunk left open by a panicking closure.
+    fn drop(&mut self) {
+        if self.end > self.base {
+            self.close();
+        }
+    }
+}
+
+/// An iterator that drains elements from a `SignVec`.
+///
+/// The drained elements are already out of the `SignVec` when the iterator is created; it
+/// yields them from either end and drops whatever is left when it goes out of scope.
+pub struct SignVecDrain<'a, T, S = IndexSet>
+where
+    T: 'a + Clone + Signable,
+    S: IndexBackend,
+{
+    /// A mutable reference to the `SignVec` being drained.
+    sign_vec: &'a mut SignVec<T, S>,
+    /// The position, past the end of `vals`, of the next element to yield from the front.
+    front: usize,
+    /// One past the position of the next element to yield from the back.
+    back: usize,
+}
+
+impl<'a, T, S> Iterator for SignVecDrain<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    type Item = T;
+
+    /// Advances the iterator and returns the next item.
+    ///
+    /// This method returns `Some(item)` if there are more items to process,
+    /// otherwise it returns `None`.
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        // SAFETY: `front` lies in the spare capacity holding the drained elements, and it is
+        // advanced past the element so that it is never read or dropped again.
+        let item = unsafe { std::ptr::read(self.sign_vec.vals.as_ptr().add(self.front)) };
+        self.front += 1;
+        Some(item)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, S> DoubleEndedIterator for SignVecDrain<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    #[inline(always)]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        // SAFETY: as in `next`, reading from the other end.
+        Some(unsafe { std::ptr::read(self.sign_vec.vals.as_ptr().add(self.back)) })
+    }
+}
+
+impl<'a, T, S> ExactSizeIterator for SignVecDrain<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+}
+
+impl<'a, T, S> FusedIterator for SignVecDrain<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+}
+
+impl<'a, T, S> Drop for SignVecDrain<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Drops the elements that were not yielded.
+    fn drop(&mut self) {
+        // SAFETY: the elements in `front..back` were neither yielded nor dropped yet, and the
+        // vector no longer counts them in its length.
+        unsafe {
+            let rest = self.sign_vec.vals.as_mut_ptr().add(self.front);
+            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                rest,
+                self.back - self.front,
+            ));
+        }
+    }
+}
+
+/// An iterator that removes the elements of a `SignVec` matching a predicate, created by
+/// `SignVec::extract_if`.
+pub struct SignVecExtractIf<'a, T, F, S = IndexSet>
+where
+    T: 'a + Signable + Clone,
+    F: FnMut(&mut T) -> bool,
+    S: IndexBackend,
+{
+    /// The `SignVec` being filtered, left empty while the iterator is alive.
+    sign_vec: &'a mut SignVec<T, S>,
+    /// The contents of `sign_vec`. The length of `vals` is zero until the iterator is dropped.
+    taken: SignVec<T, S>,
+    /// The predicate selecting the elements to extract.
+    pred: F,
+    /// Whether the elements that stay are classified again.
+    reclassify: bool,
+    /// The index of the next element to visit.
+    idx: usize,
+    /// The end of the range to visit.
+    end: usize,
+    /// The number of elements extracted so far.
+    del: usize,
+    /// The length of `vals` before filtering.
+    old_len: usize,
+}
+
+impl<'a, T, F, S> Iterator for SignVecExtractIf<'a, T, F, S>
+where
+    T: Signable + Clone,
+    F: FnMut(&mut T) -> bool,
+    S: IndexBackend,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let base = self.taken.vals.as_mut_ptr();
+        while self.idx < self.end {
+            let i = self.idx;
+            // SAFETY: `i < old_len`, and elements at or after `idx` have not been moved.
+            let val = unsafe { &mut *base.add(i) };
+            let old = self.taken.bucket_of(i);
+            if (self.pred)(val) {
+                self.idx += 1;
+                self.del += 1;
+                bucket!(self.taken, old).remove(&i);
+                // SAFETY: the element is now behind `idx` and counted in `del`, so it is never
+                // read again.
+                return Some(unsafe { std::ptr::read(val) });
+            }
+            let new = match self.reclassify {
+                true => self.taken.next_bucket(old, val),
+                false => old,
+            };
+            self.idx += 1;
+            if self.del > 0 || new != old {
+                let dst = i - self.del;
+                if self.del > 0 {
+                    // SAFETY: `dst` lies in the hole left by extracted elements.
+                    unsafe { std::ptr::copy_nonoverlapping(base.add(i), base.add(dst), 1) };
+                }
+                bucket!(self.taken, old).remove(&i);
+                bucket!(self.taken, new).insert(dst);
+            }
+        }
+        None
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.idx))
+    }
+}
+
+impl<'a, T, F, S> Drop for SignVecExtractIf<'a, T, F, S>
+where
+    T: Signable + Clone,
+    F: FnMut(&mut T) -> bool,
+    S: IndexBackend,
+{
+    /// Closes the hole left by the extracted elements and hands the contents back.
+    fn drop(&mut self) {
+        let (idx, del) = (self.idx, self.del);
+        // SAFETY: the unvisited elements in `idx..old_len` move down over the hole, after which
+        // the first `old_len - del` slots are initialized.
+        unsafe {
+            let base = self.taken.vals.as_mut_ptr();
+            if del > 0 {
+                std::ptr::copy(base.add(idx), base.add(idx - del), self.old_len - idx);
+            }
+            self.taken.vals.set_len(self.old_len - del);
+        }
+        if del > 0 {
+            self.taken.pos.remove_range_shift(idx - del, idx);
+            self.taken.neg.remove_range_shift(idx - del, idx);
+            self.taken.zero.remove_range_shift(idx - del, idx);
+            self.taken.nan.remove_range_shift(idx - del, idx);
+        }
+        std::mem::swap(self.sign_vec, &mut self.taken);
+    }
+}
+
+/// A bulk update of a `SignVec`, created by `SignVec::begin_bulk`.
+///
+/// Writes go straight into the elements while the index sets are left as they were; every index
+/// handed out for writing is recorded, and `commit` classifies those indices again. Dropping the
+/// update commits it as well, without reporting errors. The elements can be read through `Deref`
+/// in the meantime, but the `SignVec` itself, and so its index sets, stays borrowed until the
+/// update ends.
+///
+/// The elements and the index sets are held by the update and handed back when it ends. Leaking
+/// the update leaves the `SignVec` empty, with its elements leaked, but never with index sets
+/// that do not match its elements.
+pub struct SignVecBulk<'a, T, S = IndexSet>
+where
+    T: 'a + Signable + Clone,
+    S: IndexBackend,
+{
+    /// The `SignVec` being updated, empty until the update ends.
+    sign_vec: &'a mut SignVec<T, S>,
+    /// The elements of the `SignVec`.
+    vals: Vec<T>,
+    /// The index sets of the `SignVec`, in the order of the `Bucket` variants, until they are
+    /// handed back.
+    sets: Option<[S; 4]>,
+    /// The indices handed out for writing, possibly repeated.
+    dirty: Vec<usize>,
+    /// Under `NanPolicy::Reject`, the value each entry of `dirty` had when it was handed out.
+    saved: Vec<T>,
+    /// Set once the dirty list would outgrow the vector, after which commit runs a full `sync`.
+    all: bool,
+}
+
+impl<'a, T, S> SignVecBulk<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Ends the update, classifying the touched elements again.
+    ///
+    /// Elements in the hysteresis band keep the side they were on, as with `SignVec::set`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SignVecError::NanRejected` for the first touched element that became a NaN if
+    /// the policy is `NanPolicy::Reject`. Each such element gets back the value it had before
+    /// the update, while the other elements keep their new values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{NanPolicy, SignVec, SignVecError};
+    ///
+    /// let mut sign_vec = SignVec::with_nan_policy(NanPolicy::Reject);
+    /// sign_vec.extend_from_slice(&[1.0, 2.0, 3.0]);
+    ///
+    /// let mut bulk = sign_vec.begin_bulk();
+    /// bulk.set(0, -1.0);
+    /// bulk.set(2, f64::NAN);
+    ///
+    /// assert_eq!(bulk.commit(), Err(SignVecError::NanRejected { index: 2 }));
+    /// assert_eq!(sign_vec.as_slice(), &[-1.0, 2.0, 3.0]);
+    /// ```
+    #[inline(always)]
+    pub fn commit(mut self) -> Result<(), SignVecError> {
+        self.finish()
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it is out of bounds,
+    /// and marks it for reclassification.
+    #[inline(always)]
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx < self.vals.len() {
+            self.touch(idx, idx + 1);
+        }
+        self.vals.get_mut(idx)
+    }
+
+    /// Writes `val` at `idx` and marks it for reclassification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    #[inline(always)]
+    pub fn set(&mut self, idx: usize, val: T) {
+        match self.get_mut(idx) {
+            Some(slot) => *slot = val,
+            None => panic!(
+                "Index out of bounds: index {} length {}",
+                idx,
+                self.vals.len()
+            ),
+        }
+    }
+
+    /// Returns the elements in `range` as a mutable slice and marks all of them for
+    /// reclassification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    #[inline(always)]
+    pub fn slice_mut<R>(&mut self, range: R) -> &mut [T]
+    where
+        R: RangeBounds<usize>,
+    {
+        let len = self.vals.len();
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&e) => e + 1,
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => len,
+        };
+        if start > end || end > len {
+            panic!("Invalid range for slice_mut");
+        }
+        self.touch(start, end);
+        &mut self.vals[start..end]
+    }
+
+    /// Records `start..end` as touched, giving up on the list for a full `sync` once it holds
+    /// as many entries as the vector has elements. A hysteresis band needs the old sets, and
+    /// `NanPolicy::Reject` the old values, so the list is always kept with either.
+    #[inline(always)]
+    fn touch(&mut self, start: usize, end: usize) {
+        if self.all {
+            return;
+        }
+        let reject = self.sign_vec.nan_policy == NanPolicy::Reject;
+        if self.sign_vec.dead_band.is_none()
+            && !reject
+            && self.dirty.len() + (end - start) >= self.vals.len()
+        {
+            self.all = true;
+            self.dirty = Vec::new();
+        } else {
+            self.dirty.extend(start..end);
+            if reject {
+                self.saved.extend_from_slice(&self.vals[start..end]);
+            }
+        }
+    }
+
+    /// Hands the elements and index sets back to the `SignVec` and classifies the touched
+    /// elements again, putting back the old value of each rejected NaN. Does nothing once the
+    /// update has ended.
+    fn finish(&mut self) -> Result<(), SignVecError> {
+        let Some([pos, neg, zero, nan]) = self.sets.take() else {
+            return Ok(());
+        };
+        let sign_vec = &mut *self.sign_vec;
+        sign_vec.vals = std::mem::take(&mut self.vals);
+        sign_vec.pos = pos;
+        sign_vec.neg = neg;
+        sign_vec.zero = zero;
+        sign_vec.nan = nan;
+        if self.all {
+            sign_vec.sync();
+            return Ok(());
+        }
+        let mut result = Ok(());
+        // The first entry for an index saved its value from before the update.
+        for (&idx, saved) in self.dirty.iter().zip(&self.saved) {
+            if sign_vec.vals[idx].is_nan() {
+                sign_vec.vals[idx] = saved.clone();
+                result = result.and(Err(SignVecError::NanRejected { index: idx }));
+            }
+        }
+        for &idx in &self.dirty {
+            let old = sign_vec.bucket_of(idx);
+            let new = sign_vec.next_bucket(old, &sign_vec.vals[idx]);
+            if old != new {
+                bucket!(sign_vec, old).remove(&idx);
+                bucket!(sign_vec, new).insert(idx);
+            }
+        }
+        result
+    }
+}
+
+impl<'a, T, S> Deref for SignVecBulk<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.vals
+    }
+}
+
+impl<'a, T, S> Drop for SignVecBulk<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Ends the update as `commit` does, without reporting rejected NaNs.
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// A mutable reference to an element of a `SignVec` that files the element under its new
+/// sign when dropped.
+///
+/// Returned by `SignVec::get_mut`, `SignVec::entry` and the iterator over `&mut SignVec`. The
+/// guard takes the element's index out of its set and dereferences to the element; dropping the
+/// guard files the index under the element's new sign. Leaking a guard, with
+/// `std::mem::forget`, leaves the element in no set until the next `sync`, but never filed
+/// under the wrong sign.
+///
+/// Under `NanPolicy::Reject` the guard holds a clone of the element, to restore it should it
+/// become a NaN; otherwise the element is never cloned.
+///
+/// # Panics
+///
+/// Dropping the guard panics if the element became a NaN and the policy is
+/// `NanPolicy::Reject`. The element then keeps its old value; a guard dropped while already
+/// panicking does the same without panicking again.
+pub struct SignVecMut<'a, T, S = IndexSet>
+where
+    T: 'a + Signable + Clone,
+    S: IndexBackend,
+{
+    /// The `SignVec` the element belongs to. Only its rules and index sets are reached through
+    /// it, never its elements.
+    sign_vec: *mut SignVec<T, S>,
+    /// The element.
+    val: &'a mut T,
+    /// The index of the element.
+    idx: usize,
+    /// The bucket the element was filed under.
+    old: Bucket,
+    /// Whether the index was taken out of its set when the guard was created.
+    unfiled: bool,
+    /// The element before the update, kept under `NanPolicy::Reject` only.
+    saved: Option<T>,
+}
+
+impl<'a, T, S> SignVecMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Creates a guard for the element at `idx` of `sign_vec`, taking its index out of its set
+    /// if `unfile` is set.
     ///
-    /// ```
-    /// use signvec::SignVec;
+    /// # Safety
     ///
-    /// let sign_vec: SignVec<f64> = SignVec::with_capacity(10);
-    /// assert!(sign_vec.is_empty());
-    /// ```
+    /// `idx` must be in bounds and handed to no other live guard, and `sign_vec` must stay
+    /// exclusively borrowed by the guard, or by the iterator making it, for `'a`. Without
+    /// `unfile`, the guard must be dropped, not leaked.
     #[inline(always)]
-    pub fn with_capacity(capacity: usize) -> Self {
+    unsafe fn new(sign_vec: *mut SignVec<T, S>, idx: usize, unfile: bool) -> Self {
+        let old = (*sign_vec).bucket_of(idx);
+        if unfile {
+            bucket!((*sign_vec), old).remove(&idx);
+        }
+        let val = &mut *(*sign_vec).vals.as_mut_ptr().add(idx);
+        let saved = match (*sign_vec).nan_policy {
+            NanPolicy::Reject => Some(val.clone()),
+            _ => None,
+        };
         Self {
-            vals: Vec::with_capacity(capacity),
-            pos: Set::with_max(capacity),
-            neg: Set::with_max(capacity),
-            _marker: PhantomData,
+            sign_vec,
+            val,
+            idx,
+            old,
+            unfiled: unfile,
+            saved,
         }
     }
+
+    /// Returns the index of the element.
+    #[inline(always)]
+    pub fn index(&self) -> usize {
+        self.idx
+    }
 }
 
-/// An iterator that drains elements from a `SignVec`.
+impl<'a, T, S> Deref for SignVecMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.val
+    }
+}
+
+impl<'a, T, S> DerefMut for SignVecMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.val
+    }
+}
+
+impl<'a, T, S> fmt::Debug for SignVecMut<'a, T, S>
+where
+    T: Signable + Clone + fmt::Debug,
+    S: IndexBackend,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignVecMut")
+            .field("idx", &self.idx)
+            .field("val", &self.val)
+            .finish()
+    }
+}
+
+impl<'a, T, S> Drop for SignVecMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    /// Files the element under its new sign, restoring it first if it was rejected as a NaN.
+    fn drop(&mut self) {
+        let idx = self.idx;
+        let mut rejected = None;
+        if let Some(saved) = self.saved.take() {
+            if self.val.is_nan() {
+                *self.val = saved;
+                rejected = Some(SignVecError::NanRejected { index: idx });
+            }
+        }
+        // SAFETY: the `SignVec` is exclusively borrowed by this guard, or by the iterator that
+        // made it, for `'a`. Only its rules and index sets are reached here, never the elements
+        // other guards point into. Guards are neither `Send` nor `Sync`, so no two of them
+        // update the sets at once.
+        unsafe {
+            let sign_vec = self.sign_vec;
+            let new = match rejected {
+                Some(_) => self.old,
+                None => (*sign_vec).next_bucket(self.old, self.val),
+            };
+            if self.unfiled {
+                bucket!((*sign_vec), new).insert(idx);
+            } else if new != self.old {
+                bucket!((*sign_vec), self.old).remove(&idx);
+                bucket!((*sign_vec), new).insert(idx);
+            }
+        }
+        if let Some(err) = rejected {
+            // Panicking while already unwinding would abort.
+            if !std::thread::panicking() {
+                panic!("{}", err);
+            }
+        }
+    }
+}
+
+/// An iterator over guards for the elements of a `SignVec`, created by
+/// `SignVec::iter_mut_tracked` or by iterating over `&mut SignVec`.
 ///
-/// This iterator yields elements from a `SignVec`, removing them and adjusting the
-/// internal positive and negative sets accordingly.
-pub struct SignVecDrain<'a, T: 'a + Clone + Signable> {
-    /// A mutable reference to the `SignVec` being drained.
-    sign_vec: &'a mut SignVec<T>,
-    /// The current index being processed during draining.
-    current_index: usize,
-    /// The end index of the drain operation.
-    drain_end: usize,
+/// Each guard files its element under the new sign when dropped, so the index sets are up to
+/// date as soon as the guards are.
+pub struct SignVecIterMut<'a, T, S = IndexSet>
+where
+    T: 'a + Signable + Clone,
+    S: IndexBackend,
+{
+    /// The `SignVec` being iterated over.
+    sign_vec: *mut SignVec<T, S>,
+    /// The index of the next element to yield from the front.
+    front: usize,
+    /// One past the index of the next element to yield from the back.
+    back: usize,
+    _marker: PhantomData<&'a mut SignVec<T, S>>,
 }
 
-impl<'a, T> Iterator for SignVecDrain<'a, T>
+impl<'a, T, S> SignVecIterMut<'a, T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
-    type Item = T;
+    #[inline(always)]
+    fn new(sign_vec: &'a mut SignVec<T, S>) -> Self {
+        let back = sign_vec.vals.len();
+        Self {
+            sign_vec,
+            front: 0,
+            back,
+            _marker: PhantomData,
+        }
+    }
 
-    /// Advances the iterator and returns the next item.
-    ///
-    /// This method returns `Some(item)` if there are more items to process,
-    /// otherwise it returns `None`.
+    /// Returns the guard for the element at `idx`, which is yielded only once.
+    #[inline(always)]
+    fn guard(&mut self, idx: usize) -> SignVecMut<'a, T, S> {
+        // SAFETY: the `SignVec` stays borrowed by the iterator for `'a`.
+        unsafe { SignVecMut::new(self.sign_vec, idx, true) }
+    }
+}
+
+impl<'a, T, S> Iterator for SignVecIterMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    type Item = SignVecMut<'a, T, S>;
+
+    #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_index >= self.drain_end {
+        if self.front == self.back {
             return None;
         }
+        self.front += 1;
+        Some(self.guard(self.front - 1))
+    }
 
-        // Perform the actual removal.
-        let result = self.sign_vec.vals.remove(self.current_index);
-        // No need to adjust self.current_index as we always remove the current element.
-
-        // Update pos and neg to reflect the removal.
-        // Since we are always removing the current element, we just need to update subsequent indices.
-        // Remove the current index from pos or neg if present.
-        self.sign_vec.pos.remove(&self.current_index);
-        self.sign_vec.neg.remove(&self.current_index);
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
 
-        // Adjust indices for remaining elements in pos and neg.
-        self.sign_vec.pos = self
-            .sign_vec
-            .pos
-            .iter()
-            .map(|&i| if i > self.current_index { i - 1 } else { i })
-            .collect();
-        self.sign_vec.neg = self
-            .sign_vec
-            .neg
-            .iter()
-            .map(|&i| if i > self.current_index { i - 1 } else { i })
-            .collect();
+impl<'a, T, S> DoubleEndedIterator for SignVecIterMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+    #[inline(always)]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.guard(self.back))
+    }
+}
 
-        // Adjust the drain_end since the vector's length has decreased by one.
-        self.drain_end -= 1;
+impl<'a, T, S> ExactSizeIterator for SignVecIterMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
+}
 
-        Some(result)
-    }
+impl<'a, T, S> FusedIterator for SignVecIterMut<'a, T, S>
+where
+    T: Signable + Clone,
+    S: IndexBackend,
+{
 }
 
 #[derive(Debug)]
-pub struct SignVecValues<'a, T>
+pub struct SignVecValues<'a, T, S = IndexSet>
 where
     T: 'a + Signable + Clone,
+    S: 'a + IndexBackend,
 {
     // Store a raw pointer to the vector's data.
     vals_ptr: *const T,
-    indices_iter: std::slice::Iter<'a, usize>,
+    indices_iter: S::Iter<'a>,
 }
 
-impl<'a, T> SignVecValues<'a, T>
+impl<'a, T, S> SignVecValues<'a, T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     #[inline(always)]
-    pub fn new(sign_vec: &'a SignVec<T>, sign: Sign) -> Self {
-        // Obtain a raw pointer to the data of the `vals` vector.
-        let vals_ptr = sign_vec.vals.as_ptr();
-        let indices_iter = match sign {
-            Sign::Plus => (&sign_vec.pos).into_iter(),
-            Sign::Minus => (&sign_vec.neg).into_iter(),
-        };
+    pub fn new(sign_vec: &'a SignVec<T, S>, sign: Sign) -> Self {
+        Self::from_set(sign_vec, sign_vec.indices(sign))
+    }
+
+    /// Creates an iterator over the values of `sign_vec` whose indices are in `set`.
+    #[inline(always)]
+    fn from_set(sign_vec: &'a SignVec<T, S>, set: &'a S) -> Self {
         SignVecValues {
-            vals_ptr,
-            indices_iter,
+            // Obtain a raw pointer to the data of the `vals` vector.
+            vals_ptr: sign_vec.vals.as_ptr(),
+            indices_iter: set.iter(),
         }
     }
 }
 
-impl<'a, T> Iterator for SignVecValues<'a, T>
+impl<'a, T, S> Iterator for SignVecValues<'a, T, S>
 where
     T: 'a + Signable + Clone,
+    S: 'a + IndexBackend,
 {
     type Item = &'a T;
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        self.indices_iter.next().map(|&idx|
+        self.indices_iter.next().map(|idx|
             // Safety: The index is assumed to be within bounds, as indices are managed internally
             // and should be valid for the `vals` vector. Accessing the vector's elements
             // via a raw pointer obtained from `as_ptr` assumes that no concurrent modifications
@@ -1783,9 +4032,10 @@ where
 }
 
 /// Allows accessing the underlying vector reference of a `SignVec`.
-impl<T> AsRef<Vec<T>> for SignVec<T>
+impl<T, S> AsRef<Vec<T>> for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Returns a reference to the underlying vector.
     fn as_ref(&self) -> &Vec<T> {
@@ -1800,23 +4050,30 @@ impl<T> Default for SignVec<T>
 where
     T: Signable + Clone,
 {
-    /// Creates a new empty `SignVec` with a default capacity.
+    /// Creates a new empty `SignVec`.
     ///
-    /// The default capacity is determined by the `DEFAULT_SET_SIZE` constant.
+    /// Like `Vec::new`, it allocates nothing until elements are added, and the index sets grow
+    /// along with the elements.
     fn default() -> Self {
         Self {
             vals: Vec::default(),
-            pos: Set::with_max(DEFAULT_SET_SIZE),
-            neg: Set::with_max(DEFAULT_SET_SIZE),
+            pos: IndexSet::new(),
+            neg: IndexSet::new(),
+            zero: IndexSet::new(),
+            nan: IndexSet::new(),
+            nan_policy: NanPolicy::default(),
+            classifier: None,
+            dead_band: None,
             _marker: PhantomData,
         }
     }
 }
 
 /// Implements extending functionality for references to items.
-impl<'a, T> Extend<&'a T> for SignVec<T>
+impl<'a, T, S> Extend<&'a T> for SignVec<T, S>
 where
     T: Signable + Clone + 'a,
+    S: IndexBackend,
 {
     /// Extends the `SignVec` with items from an iterator over references to items.
     ///
@@ -1850,23 +4107,19 @@ where
     {
         for item in iter {
             let index = self.vals.len(); // Get the current length before pushing
+            let bucket = self.rules().bucket(item);
+            self.grow(1);
+            bucket!(self, bucket).insert(index);
             self.vals.push(item.clone()); // Clone the item and push it onto vals
-            match item.sign() {
-                Sign::Plus => {
-                    self.pos.insert(index);
-                }
-                Sign::Minus => {
-                    self.neg.insert(index);
-                }
-            }
         }
     }
 }
 
 /// Implements extending functionality for owned items.
-impl<T> Extend<T> for SignVec<T>
+impl<T, S> Extend<T> for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Extends the `SignVec` with items from an iterator over owned items.
     ///
@@ -1901,14 +4154,9 @@ where
     {
         for item in iter {
             let index = self.vals.len(); // Get the current length before pushing
-            match item.sign() {
-                Sign::Plus => {
-                    self.pos.insert(index);
-                }
-                Sign::Minus => {
-                    self.neg.insert(index);
-                }
-            }
+            let bucket = self.rules().bucket(&item);
+            self.grow(1);
+            bucket!(self, bucket).insert(index);
             self.vals.push(item); // Push the item onto vals
         }
     }
@@ -2013,7 +4261,7 @@ impl<T> From<Vec<T>> for SignVec<T>
 where
     T: Signable + Clone,
 {
-    /// Converts a vector into a `SignVec`, moving its elements.
+    /// Converts a vector into a `SignVec`, moving its elements and filing them 64 at a time.
     ///
     /// # Examples
     ///
@@ -2024,13 +4272,14 @@ where
     /// let sign_vec: SignVec<_> = vec.into();
     /// ```
     fn from(vec: Vec<T>) -> Self {
-        vec.into_iter().collect()
+        Self::from_vals(vec, NanPolicy::default())
     }
 }
 
-impl<T> From<SignVec<T>> for Vec<T>
+impl<T, S> From<SignVec<T, S>> for Vec<T>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Converts a `SignVec` into a `Vec`, moving its elements.
     ///
@@ -2042,14 +4291,15 @@ where
     /// let sv = svec![1, 2, 3, 4, 5];
     /// let vec: Vec<_> = Vec::from(&sv);
     /// ```
-    fn from(sign_vec: SignVec<T>) -> Self {
+    fn from(sign_vec: SignVec<T, S>) -> Self {
         sign_vec.vals.clone()
     }
 }
 
-impl<T> From<&SignVec<T>> for Vec<T>
+impl<T, S> From<&SignVec<T, S>> for Vec<T>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Converts a reference to `SignVec` into a `Vec`, cloning its elements.
     ///
@@ -2062,7 +4312,7 @@ where
     /// let vec: Vec<i32> = Vec::from(&sign_vec);
     ///
     /// ```
-    fn from(sign_vec: &SignVec<T>) -> Self {
+    fn from(sign_vec: &SignVec<T, S>) -> Self {
         sign_vec.vals.clone()
     }
 }
@@ -2086,11 +4336,13 @@ where
     }
 }
 
-impl<T> FromIterator<T> for SignVec<T>
+impl<T, S> FromIterator<T> for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
-    /// Constructs a `SignVec` from an iterator, cloning each element.
+    /// Constructs a `SignVec` from an iterator, collecting the elements first and then filing
+    /// them 64 at a time.
     ///
     /// # Examples
     ///
@@ -2101,31 +4353,14 @@ where
     /// let sign_vec: SignVec<_> = iter.collect();
     /// ```
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut vec = Vec::new();
-        let mut pos = Set::with_max(DEFAULT_SET_SIZE);
-        let mut neg = Set::with_max(DEFAULT_SET_SIZE);
-
-        for (i, item) in iter.into_iter().enumerate() {
-            if item.sign() == Sign::Plus {
-                pos.insert(i);
-            } else {
-                neg.insert(i);
-            }
-            vec.push(item);
-        }
-
-        SignVec {
-            vals: vec,
-            pos,
-            neg,
-            _marker: PhantomData,
-        }
+        Self::from_vals(iter.into_iter().collect(), NanPolicy::default())
     }
 }
 
-impl<'a, T> FromIterator<&'a T> for SignVec<T>
+impl<'a, T, S> FromIterator<&'a T> for SignVec<T, S>
 where
     T: 'a + Signable + Clone,
+    S: IndexBackend,
 {
     /// Constructs a `SignVec` from an iterator of references, cloning each element.
     ///
@@ -2135,35 +4370,17 @@ where
     /// use signvec::SignVec;
     ///
     /// let iter = vec![&1, &-2, &3, &-4, &5].into_iter();
-    /// let sign_vec: SignVec<_> = iter.collect();
+    /// let sign_vec: SignVec<i32> = iter.collect();
     /// ```
     fn from_iter<I: IntoIterator<Item = &'a T>>(iter: I) -> Self {
-        let mut vec = Vec::new();
-        let mut pos = Set::with_max(DEFAULT_SET_SIZE);
-        let mut neg = Set::with_max(DEFAULT_SET_SIZE);
-
-        for (i, item) in iter.into_iter().enumerate() {
-            let cloned_item = item.clone();
-            if cloned_item.sign() == Sign::Plus {
-                pos.insert(i);
-            } else {
-                neg.insert(i);
-            }
-            vec.push(cloned_item);
-        }
-
-        SignVec {
-            vals: vec,
-            pos,
-            neg,
-            _marker: PhantomData,
-        }
+        Self::from_vals(iter.into_iter().cloned().collect(), NanPolicy::default())
     }
 }
 
-impl<T> IntoIterator for SignVec<T>
+impl<T, S> IntoIterator for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     type Item = T;
     type IntoIter = ::std::vec::IntoIter<T>;
@@ -2187,9 +4404,10 @@ where
     }
 }
 
-impl<'a, T> IntoIterator for &'a SignVec<T>
+impl<'a, T, S> IntoIterator for &'a SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     type Item = &'a T;
     type IntoIter = ::std::slice::Iter<'a, T>;
@@ -2213,35 +4431,40 @@ where
     }
 }
 
-impl<'a, T> IntoIterator for &'a mut SignVec<T>
+impl<'a, T, S> IntoIterator for &'a mut SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
-    type Item = &'a mut T;
-    type IntoIter = ::std::slice::IterMut<'a, T>;
+    type Item = SignVecMut<'a, T, S>;
+    type IntoIter = SignVecIterMut<'a, T, S>;
 
-    /// Converts the `SignVec` into an iterator yielding mutable references to elements.
+    /// Converts the `SignVec` into an iterator yielding guards for its elements.
+    ///
+    /// Each guard dereferences to its element and files it under its new sign when dropped.
     ///
     /// # Examples
     ///
     /// ```
-    /// use signvec::{SignVec, svec};
+    /// use signvec::{Sign, SignVec, svec};
     ///
     /// let mut sign_vec = SignVec::from(vec![1, -2, 3]);
-    /// for elem in &mut sign_vec {
+    /// for mut elem in &mut sign_vec {
     ///    *elem += 1;
     /// }
     /// assert_eq!(sign_vec, svec![2, -1, 4]);
+    /// assert_eq!(sign_vec.count(Sign::Plus), 2);
     /// ```
     fn into_iter(self) -> Self::IntoIter {
-        self.vals.iter_mut()
+        self.iter_mut_tracked()
     }
 }
 
 // Allowing indexing into SignVec to get a reference to an element
-impl<T> Index<usize> for SignVec<T>
+impl<T, S> Index<usize> for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     type Output = T;
 
@@ -2267,9 +4490,10 @@ where
 }
 
 // Allowing dereferencing to a slice of SignVec values
-impl<T> Deref for SignVec<T>
+impl<T, S> Deref for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     type Target = [T];
 
@@ -2288,9 +4512,10 @@ where
     }
 }
 
-impl<T> Borrow<[T]> for SignVec<T>
+impl<T, S> Borrow<[T]> for SignVec<T, S>
 where
     T: Signable + Clone,
+    S: IndexBackend,
 {
     /// Borrows the SignVec as a slice of its values.
     ///
@@ -2308,9 +4533,10 @@ where
     }
 }
 
-impl<T> Hash for SignVec<T>
+impl<T, S> Hash for SignVec<T, S>
 where
     T: Signable + Clone + Hash,
+    S: IndexBackend,
 {
     /// Computes the hash value for the SignVec.
     ///
@@ -2335,9 +4561,10 @@ where
     }
 }
 
-impl<T> Ord for SignVec<T>
+impl<T, S> Ord for SignVec<T, S>
 where
     T: Signable + Clone + Ord,
+    S: IndexBackend,
 {
     /// Compares two SignVecs lexicographically.
     ///
@@ -2356,9 +4583,10 @@ where
     }
 }
 
-impl<T> PartialOrd for SignVec<T>
+impl<T, S> PartialOrd for SignVec<T, S>
 where
     T: Signable + Clone + PartialOrd,
+    S: IndexBackend,
 {
     /// Compares two SignVecs lexicographically.
     ///
@@ -2377,11 +4605,17 @@ where
     }
 }
 
-impl<T> Eq for SignVec<T> where T: Signable + Clone + Eq {}
+impl<T, S> Eq for SignVec<T, S>
+where
+    T: Signable + Clone + Eq,
+    S: IndexBackend,
+{
+}
 
-impl<T> PartialEq for SignVec<T>
+impl<T, S> PartialEq for SignVec<T, S>
 where
     T: Signable + Clone + PartialEq,
+    S: IndexBackend,
 {
     /// Checks if two SignVecs are equal.
     ///
@@ -2402,9 +4636,10 @@ where
 
 // Implementations for comparisons between SignVec and slices, mutable slices, arrays, and vectors
 
-impl<T, U> PartialEq<&[U]> for SignVec<T>
+impl<T, U, S> PartialEq<&[U]> for SignVec<T, S>
 where
     T: PartialEq<U> + Signable + Clone,
+    S: IndexBackend,
 {
     /// Checks if a SignVec is equal to a slice.
     ///
@@ -2422,9 +4657,10 @@ where
     }
 }
 
-impl<T, U> PartialEq<&mut [U]> for SignVec<T>
+impl<T, U, S> PartialEq<&mut [U]> for SignVec<T, S>
 where
     T: PartialEq<U> + Signable + Clone,
+    S: IndexBackend,
 {
     /// Checks if a SignVec is equal to a mutable slice.
     ///
@@ -2437,15 +4673,15 @@ where
     ///
     /// assert_eq!(vec, &[1.0, -2.0, 3.0] as &[f64]);
     /// ```
-
     fn eq(&self, other: &&mut [U]) -> bool {
         self.vals.eq(*other)
     }
 }
 
-impl<T, U, const N: usize> PartialEq<[U; N]> for SignVec<T>
+impl<T, U, S, const N: usize> PartialEq<[U; N]> for SignVec<T, S>
 where
     T: PartialEq<U> + Signable + Clone,
+    S: IndexBackend,
 {
     /// Checks if a SignVec is equal to an array.
     ///
@@ -2463,9 +4699,10 @@ where
     }
 }
 
-impl<T, U> PartialEq<Vec<U>> for SignVec<T>
+impl<T, U, S> PartialEq<Vec<U>> for SignVec<T, S>
 where
     T: PartialEq<U> + Signable + Clone,
+    S: IndexBackend,
 {
     /// Checks if a SignVec is equal to a vector.
     ///
@@ -2486,8 +4723,8 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::svec;
-    use fastset::set;
+    use crate::{svec, NoneAsMinus, NoneAsPlus, SignBit};
+    use fastset::{set, Set};
     use std::collections::HashSet;
 
     #[derive(Clone, Eq, PartialEq, Default)]
@@ -2500,55 +4737,613 @@ mod tests {
             Account { balance }
         }
 
-        fn balance(&self) -> i32 {
-            self.balance
+        fn balance(&self) -> i32 {
+            self.balance
+        }
+    }
+
+    impl Signable for Account {
+        fn sign(&self) -> Sign {
+            if self.balance >= 0 {
+                Sign::Plus
+            } else {
+                Sign::Minus
+            }
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        // Test appending positive elements
+        let mut vec = svec![-1];
+        let other = vec![2, -3];
+        vec.append(&other);
+        assert_eq!(vec.as_slice(), &[-1, 2, -3]);
+        assert_eq!(vec.count(Sign::Plus), 1);
+        assert_eq!(vec.count(Sign::Minus), 2);
+
+        // Test appending negative elements
+        let mut vec = svec![];
+        let other = vec![-2, 3];
+        vec.append(&other);
+        assert_eq!(vec.as_slice(), &[-2, 3]);
+        assert_eq!(vec.count(Sign::Plus), 1);
+        assert_eq!(vec.count(Sign::Minus), 1);
+    }
+
+    #[test]
+    fn test_as_ptr() {
+        let vec: SignVec<i32> = svec![1, -2, 3];
+        let ptr = vec.as_ptr();
+        unsafe {
+            assert_eq!(*ptr, 1); // First element
+            assert_eq!(*ptr.offset(1), -2); // Second element
+            assert_eq!(*ptr.offset(2), 3); // Third element
+        }
+    }
+    #[test]
+    fn test_as_slice() {
+        let vec = svec![1, -2, 3];
+        let slice = vec.as_slice();
+        assert_eq!(slice, &[1, -2, 3]);
+    }
+
+    #[test]
+    fn test_begin_bulk() {
+        let mut vec: SignVec<i32> = (0..100).map(|i| i - 50).collect();
+        let mut bulk = vec.begin_bulk();
+        bulk.set(0, 7);
+        bulk.set(0, -7);
+        *bulk.get_mut(60).unwrap() = -1;
+        assert!(bulk.get_mut(100).is_none());
+        bulk.slice_mut(70..=71).fill(0);
+        assert_eq!(bulk[60], -1);
+        bulk.commit().unwrap();
+        let expected: SignVec<i32> = vec.iter().copied().collect();
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(vec.indices(sign), expected.indices(sign));
+        }
+        assert!(vec.indices(Sign::Zero).contains(&71));
+
+        // Touching every element falls back to a full sync, also when the update is dropped.
+        {
+            let mut bulk = vec.begin_bulk();
+            bulk.slice_mut(..).iter_mut().for_each(|x| *x = -*x);
+            bulk.set(3, 0);
+        }
+        assert_eq!(vec.count(Sign::Minus), 46);
+        assert!(vec.indices(Sign::Zero).contains(&3));
+
+        // The hysteresis band applies to the touched elements.
+        let mut vec = svec![1.0, -1.0];
+        vec.set_hysteresis(Some(0.5));
+        let mut bulk = vec.begin_bulk();
+        bulk.slice_mut(..).copy_from_slice(&[-0.2, 0.2]);
+        bulk.commit().unwrap();
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+
+        // Rejected NaNs get their old values back, also when the update is dropped during a
+        // panic, and a leaked update leaves an empty vector behind.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.extend_from_slice(&[1.0, -2.0, 3.0, 4.0]);
+        let mut bulk = vec.begin_bulk();
+        bulk.set(1, 5.0);
+        bulk.slice_mut(1..3).fill(f64::NAN);
+        bulk.set(0, -1.0);
+        assert_eq!(bulk.commit(), Err(SignVecError::NanRejected { index: 1 }));
+        assert_eq!(vec.as_slice(), &[-1.0, -2.0, 3.0, 4.0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut bulk = vec.begin_bulk();
+            bulk.set(3, f64::NAN);
+            bulk.set(2, -3.0);
+            panic!("stop");
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &[-1.0, -2.0, -3.0, 4.0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![3]);
+        let mut bulk = vec.begin_bulk();
+        bulk.set(0, 1.0);
+        std::mem::forget(bulk);
+        assert!(vec.is_empty());
+        assert_eq!(vec.count(Sign::Plus) + vec.count(Sign::Minus), 0);
+    }
+
+    #[test]
+    fn test_index_capacity() {
+        fn check<S: IndexBackend>() {
+            let mut vec: SignVec<i32, S> = SignVec::with_backend(0);
+            for i in 0..20_000 {
+                vec.push(if i % 3 == 0 { -i } else { i });
+                assert!(vec.index_capacity() >= vec.capacity());
+            }
+            assert_eq!(vec.count(Sign::Minus), 6666);
+            assert_eq!(vec.count(Sign::Plus), 13_333);
+            assert!(vec.indices(Sign::Minus).contains(&19_998));
+            let mut rng = WyRand::new_seed(7);
+            let idx = vec.random(Sign::Plus, &mut rng).unwrap();
+            assert!(vec[idx] > 0);
+
+            vec.truncate(1500);
+            vec.shrink_to_fit();
+            assert!(vec.index_capacity() >= 1500);
+            assert!(vec.index_capacity() < 2000);
+            vec.push(-1);
+            assert!(vec.index_capacity() >= vec.capacity());
+            assert!(vec.indices(Sign::Minus).contains(&1500));
+        }
+
+        check::<IndexSet>();
+        check::<IndexSet<u32>>();
+        check::<crate::BitmapIndex>();
+
+        let mut vec = SignVec::new();
+        assert_eq!(vec.index_capacity(), 0);
+        vec.extend_from_slice(&[1.0; 3000]);
+        vec.resize(5000, -1.0);
+        vec.insert(0, 0.0);
+        assert!(vec.index_capacity() >= vec.capacity());
+        assert_eq!(vec.indices(Sign::Minus).len(), 2000);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut vec = svec![5, -10, 0];
+        {
+            let mut x = vec.get_mut(2).unwrap();
+            *x = -1;
+            assert_eq!(x.index(), 2);
+        }
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 2]);
+        assert_eq!(vec.count(Sign::Zero), 0);
+        assert!(vec.get_mut(3).is_none());
+
+        *vec.entry(0) = 0;
+        *vec.entry(1) *= -1;
+        assert_eq!(vec.indices(Sign::Zero), &set![0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+
+        // A leaked guard leaves its element in no set until the next `sync`.
+        let mut x = vec.entry(0);
+        *x = -5;
+        std::mem::forget(x);
+        assert_eq!(vec[0], -5);
+        assert_eq!(vec.count(Sign::Zero), 0);
+        assert_eq!(vec.indices(Sign::Minus), &set![2]);
+        vec.sync();
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+
+        // A rejected NaN is not written back, also by a guard dropped during a panic.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.extend_from_slice(&[1.0, 2.0]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            *vec.entry(0) = f64::NAN;
+        }));
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut x = vec.entry(1);
+            *x = f64::NAN;
+            panic!("stop");
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &[1.0, 2.0]);
+        assert_eq!(vec.count(Sign::Plus), 2);
+
+        // Inside the hysteresis band the element keeps its side.
+        let mut vec = svec![1.0, -1.0];
+        vec.set_hysteresis(Some(0.5));
+        *vec.entry(0) = -0.25;
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        *vec.entry(0) = -0.75;
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn test_entry_out_of_bounds() {
+        let mut vec = svec![1, 2];
+        vec.entry(2);
+    }
+
+    #[test]
+    fn test_iter_mut_guards() {
+        let mut vec: SignVec<i32, crate::BitmapIndex> = (-3..=3).collect();
+        for (i, mut x) in (&mut vec).into_iter().enumerate() {
+            assert_eq!(x.index(), i);
+            *x = -*x;
+        }
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![4, 5, 6]);
+
+        // Guards taken from both ends and dropped out of order.
+        let mut iter = (&mut vec).into_iter();
+        assert_eq!(iter.len(), 7);
+        let mut last = iter.next_back().unwrap();
+        let mut first = iter.next().unwrap();
+        *last = 9;
+        *first = 0;
+        drop(last);
+        drop(first);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 3]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 2, 6]);
+        assert_eq!(vec.indices(Sign::Minus), &set![4, 5]);
+    }
+
+    #[test]
+    fn test_update_all() {
+        let mut vec: SignVec<f64> = (0..1000).map(|i| (i % 7) as f64 - 3.0).collect();
+        let mut visited = 0;
+        vec.update_all(|i, x| {
+            assert_eq!(i, visited);
+            visited += 1;
+            *x = if i % 10 == 0 { f64::NAN } else { -*x };
+        });
+        assert_eq!(visited, 1000);
+        let expected: SignVec<f64> = vec.iter().copied().collect();
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(vec.indices(sign), expected.indices(sign));
+        }
+        assert_eq!(vec.count_nan(), 100);
+
+        // A panicking closure leaves the elements visited so far filed correctly.
+        let mut vec = svec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.update_all(|i, x| {
+                *x = -1;
+                if i == 1 {
+                    panic!("stop");
+                }
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![2]);
+
+        // A rejected NaN keeps its old value, and the elements updated before it their new one.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.extend((0..100).map(|i| i as f64 + 1.0));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.update_all(|i, x| *x = if i % 3 == 2 { f64::NAN } else { -*x })
+        }));
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(message, "NaN rejected by NanPolicy::Reject at index 2");
+        assert_eq!(vec.count_nan(), 0);
+        for (i, x) in vec.iter().enumerate() {
+            let sign = if i < 64 && i % 3 != 2 { -1.0 } else { 1.0 };
+            assert_eq!(*x, sign * (i as f64 + 1.0));
+        }
+        let expected: SignVec<f64> = vec.iter().copied().collect();
+        assert_eq!(vec.indices(Sign::Minus), expected.indices(Sign::Minus));
+        assert_eq!(vec.indices(Sign::Plus), expected.indices(Sign::Plus));
+
+        // A NaN changing its sign bit moves under `NanPolicy::SignBit`.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::SignBit);
+        vec.extend_from_slice(&[f32::NAN, 1.0]);
+        vec.update_all(|_, x| *x = -*x);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+
+        // With a hysteresis band the elements go through guards.
+        let mut vec = svec![1.0, -1.0, 2.0];
+        vec.set_hysteresis(Some(0.5));
+        vec.update_all(|_, x| *x = -*x * 0.3);
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 2]);
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let mut vec = svec![3, -1, 0, -7];
+        let (vals, sets) = unsafe { vec.raw_parts_mut() };
+        let vals = std::mem::take(vals);
+        let sets = sets.map(std::mem::take);
+        assert!(vec.is_empty() && vec.count(Sign::Minus) == 0);
+
+        let rebuilt = unsafe { SignVec::from_raw_parts(vals, sets) };
+        assert_eq!(rebuilt, svec![3, -1, 0, -7]);
+        assert_eq!(rebuilt.indices(Sign::Minus), &set![1, 3]);
+        assert_eq!(rebuilt.indices(Sign::Zero), &set![2]);
+        assert_eq!(rebuilt.nan_policy(), NanPolicy::Track);
+    }
+
+    #[test]
+    fn test_serde() {
+        use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, Token};
+
+        /// Compares the index sets and the policy along with the elements.
+        #[derive(Debug, Deserialize)]
+        #[serde(transparent)]
+        struct Checked(SignVec<f64>);
+
+        impl PartialEq for Checked {
+            fn eq(&self, other: &Self) -> bool {
+                let (a, b) = (&self.0, &other.0);
+                a == b
+                    && a.nan_policy() == b.nan_policy()
+                    && [Sign::Plus, Sign::Minus, Sign::Zero]
+                        .iter()
+                        .all(|&sign| a.indices(sign) == b.indices(sign))
+                    && a.indices_nan() == b.indices_nan()
+            }
         }
+
+        // Only the elements and the policy are written, whatever the classifier.
+        let mut vec = SignVec::with_classifier(|x: &f64| match *x > 1.0 {
+            true => Sign::Plus,
+            false => Sign::Minus,
+        });
+        vec.extend_from_slice(&[1.0, -2.0]);
+        assert_eq!(vec.count(Sign::Minus), 2);
+        let tokens = [
+            Token::Struct {
+                name: "SignVec",
+                len: 2,
+            },
+            Token::Str("vals"),
+            Token::Seq { len: Some(2) },
+            Token::F64(1.0),
+            Token::F64(-2.0),
+            Token::SeqEnd,
+            Token::Str("nan_policy"),
+            Token::UnitVariant {
+                name: "NanPolicy",
+                variant: "Track",
+            },
+            Token::StructEnd,
+        ];
+        assert_ser_tokens(&vec, &tokens);
+
+        // The sets are rebuilt from the elements, ignoring any stale ones in the input.
+        let tokens = [
+            Token::Struct {
+                name: "SignVec",
+                len: 3,
+            },
+            Token::Str("vals"),
+            Token::Seq { len: Some(3) },
+            Token::F64(1.0),
+            Token::F64(-3.0),
+            Token::F64(0.0),
+            Token::SeqEnd,
+            Token::Str("pos"),
+            Token::Seq { len: Some(1) },
+            Token::U64(2),
+            Token::SeqEnd,
+            Token::Str("nan_policy"),
+            Token::UnitVariant {
+                name: "NanPolicy",
+                variant: "SignBit",
+            },
+            Token::StructEnd,
+        ];
+        let mut expected = SignVec::with_nan_policy(NanPolicy::SignBit);
+        expected.extend_from_slice(&[1.0, -3.0, 0.0]);
+        assert_de_tokens(&Checked(expected), &tokens);
+
+        // The layout from before `nan_policy`, with the `pos` and `neg` sets, is still read,
+        // with the default policy and zeros in their own bucket.
+        let tokens = [
+            Token::Struct {
+                name: "SignVec",
+                len: 3,
+            },
+            Token::Str("vals"),
+            Token::Seq { len: Some(3) },
+            Token::F64(2.0),
+            Token::F64(-1.0),
+            Token::F64(0.0),
+            Token::SeqEnd,
+            Token::Str("pos"),
+            Token::Seq { len: Some(2) },
+            Token::U64(0),
+            Token::U64(2),
+            Token::SeqEnd,
+            Token::Str("neg"),
+            Token::Seq { len: Some(1) },
+            Token::U64(1),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ];
+        let mut expected = SignVec::new();
+        expected.extend_from_slice(&[2.0, -1.0, 0.0]);
+        assert_de_tokens(&Checked(expected), &tokens);
+
+        // A NaN is refused under `NanPolicy::Reject`.
+        let tokens = [
+            Token::Struct {
+                name: "SignVec",
+                len: 2,
+            },
+            Token::Str("vals"),
+            Token::Seq { len: Some(2) },
+            Token::F64(1.0),
+            Token::F64(f64::NAN),
+            Token::SeqEnd,
+            Token::Str("nan_policy"),
+            Token::UnitVariant {
+                name: "NanPolicy",
+                variant: "Reject",
+            },
+            Token::StructEnd,
+        ];
+        assert_de_tokens_error::<SignVec<f64>>(
+            &tokens,
+            "NaN rejected by NanPolicy::Reject at index 1",
+        );
     }
 
-    impl Signable for Account {
-        fn sign(&self) -> Sign {
-            if self.balance >= 0 {
-                Sign::Plus
-            } else {
-                Sign::Minus
+    #[test]
+    fn test_modify() {
+        let mut vec = svec![2.0, -4.0, 0.0];
+        vec.modify(0, |x| *x -= 2.0);
+        vec.update_with(1, |old| old * -1.0);
+        vec.update_with(2, |_| -1.0);
+        assert_eq!(vec, svec![0.0, 4.0, -1.0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![2]);
+
+        vec.map_in_place(|x| 1.0 - x);
+        assert_eq!(vec, svec![1.0, -3.0, 2.0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert_eq!(vec.count(Sign::Zero), 0);
+
+        // The element is changed in place, without a clone, also with a hysteresis band.
+        #[derive(Debug, PartialEq, PartialOrd)]
+        struct Counted(f64);
+        static CLONES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Counted(self.0)
             }
         }
+        impl Signable for Counted {
+            fn sign(&self) -> Sign {
+                self.0.sign()
+            }
+        }
+        impl std::ops::Neg for Counted {
+            type Output = Counted;
+            fn neg(self) -> Counted {
+                Counted(-self.0)
+            }
+        }
+        let clones = || CLONES.load(std::sync::atomic::Ordering::Relaxed);
+        let mut vec = SignVec::new();
+        vec.push(Counted(1.0));
+        vec.push(Counted(-1.0));
+        let before = clones();
+        vec.modify(0, |x| x.0 = -2.0);
+        vec.map_in_place(|x| Counted(-x.0));
+        assert_eq!(clones(), before);
+        vec.set_hysteresis(Some(Counted(0.5)));
+        let before = clones();
+        vec.update_all(|_, x| x.0 *= -0.1);
+        assert_eq!(clones(), before);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1]);
+        assert_eq!(vec.count(Sign::Minus), 0);
+
+        // A closure panicking halfway leaves the element filed under its current sign.
+        let mut vec = svec![1, 2];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.modify(1, |x| {
+                *x = -2;
+                panic!("stop");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+
+        // A NaN produced under `NanPolicy::Reject` is never stored.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.extend_from_slice(&[1.0, 0.0, -1.0]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_in_place(|x| -1.0 / x * 0.0)
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &[-0.0, 0.0, 0.0]);
+        assert_eq!(vec.count_nan(), 0);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 1, 2]);
     }
 
     #[test]
-    fn test_append() {
-        // Test appending positive elements
-        let mut vec = svec![-1];
-        let other = vec![2, -3];
-        vec.append(&other);
-        assert_eq!(vec.as_slice(), &[-1, 2, -3]);
-        assert_eq!(vec.count(Sign::Plus), 1);
-        assert_eq!(vec.count(Sign::Minus), 2);
+    #[should_panic(expected = "Index out of bounds")]
+    fn test_modify_out_of_bounds() {
+        let mut vec = svec![1, 2];
+        vec.modify(2, |x| *x += 1);
+    }
 
-        // Test appending negative elements
-        let mut vec = svec![];
-        let other = vec![-2, 3];
-        vec.append(&other);
-        assert_eq!(vec.as_slice(), &[-2, 3]);
-        assert_eq!(vec.count(Sign::Plus), 1);
-        assert_eq!(vec.count(Sign::Minus), 1);
+    #[test]
+    fn test_set_many() {
+        let mut vec = svec![1, -2, 3, 0];
+        // Index 0 flips twice, index 2 keeps its sign, index 3 leaves zero.
+        assert_eq!(vec.set_many(vec![(0, -1), (2, 4), (0, 5), (3, -3)]), 3);
+        assert_eq!(vec, svec![5, -2, 4, -3]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 3]);
+        assert_eq!(vec.count(Sign::Zero), 0);
+        assert_eq!(vec.set_many(std::iter::empty()), 0);
+
+        // An index that flips and flips back keeps its set, and its flips still count.
+        assert_eq!(vec.set_many([(3, 1), (1, 2), (3, -1)]), 3);
+        assert_eq!(vec, svec![5, 2, 4, -1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![3]);
+
+        // Many updates in any order leave the sets as a rebuild would.
+        let mut vec: SignVec<f64> = (0..500).map(|i| (i % 3) as f64 - 1.0).collect();
+        let updates: Vec<(usize, f64)> = (0..500)
+            .rev()
+            .step_by(3)
+            .map(|i| (i, 1.0 - (i % 5) as f64))
+            .collect();
+        let mut expected: Vec<f64> = vec.iter().copied().collect();
+        for &(i, x) in &updates {
+            expected[i] = x;
+        }
+        vec.set_many(updates);
+        assert_eq!(vec.as_slice(), &expected[..]);
+        let synced = SignVec::from(expected);
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(vec.indices(sign), synced.indices(sign));
+        }
+
+        // With a hysteresis band each update of an index sees the side the previous one left.
+        let mut vec = svec![1.0, -1.0];
+        vec.set_hysteresis(Some(0.5));
+        assert_eq!(vec.set_many([(0, -2.0), (0, 0.3), (1, 0.3)]), 1);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+
+        // A rejected NaN anywhere leaves the vector untouched.
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.extend_from_slice(&[1.0, 2.0]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.set_many([(0, -1.0), (1, f64::NAN)])
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec, svec![1.0, 2.0]);
+        assert_eq!(vec.count(Sign::Minus), 0);
     }
 
     #[test]
-    fn test_as_ptr() {
-        let vec: SignVec<i32> = svec![1, -2, 3];
-        let ptr = vec.as_ptr();
-        unsafe {
-            assert_eq!(*ptr, 1); // First element
-            assert_eq!(*ptr.offset(1), -2); // Second element
-            assert_eq!(*ptr.offset(2), 3); // Third element
+    #[should_panic(expected = "Index out of bounds: index 3 length 3")]
+    fn test_set_many_out_of_bounds() {
+        let mut vec = svec![1, 2, 3];
+        vec.set_many([(0, -1), (3, -1)]);
+    }
+
+    #[test]
+    fn test_set_range() {
+        let mut vec: SignVec<f64> = (0..300).map(|i| (i % 5) as f64 - 2.0).collect();
+        let new: Vec<f64> = (0..200).map(|i| 2.0 - (i % 5) as f64).collect();
+        let mut expected: Vec<f64> = vec.iter().copied().collect();
+        let flips = (0..200)
+            .filter(|&i| expected[i + 37].sign() != new[i].sign())
+            .count();
+        expected[37..237].copy_from_slice(&new);
+        assert_eq!(vec.set_range(37, &new), flips);
+        assert_eq!(vec.as_slice(), &expected[..]);
+        let synced: SignVec<f64> = expected.iter().copied().collect();
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(vec.indices(sign), synced.indices(sign));
         }
+        assert_eq!(vec.set_range(300, &[]), 0);
+
+        // With a hysteresis band small values keep their set.
+        let mut vec = svec![1.0, -1.0, 2.0];
+        vec.set_hysteresis(Some(0.5));
+        assert_eq!(vec.set_range(0, &[-0.3, 0.3, -2.0]), 1);
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 2]);
     }
+
     #[test]
-    fn test_as_slice() {
-        let vec = svec![1, -2, 3];
-        let slice = vec.as_slice();
-        assert_eq!(slice, &[1, -2, 3]);
+    #[should_panic(expected = "Invalid range for set_range")]
+    fn test_set_range_out_of_bounds() {
+        let mut vec = svec![1, 2, 3];
+        vec.set_range(2, &[4, 5]);
     }
 
     #[test]
@@ -2560,9 +5355,10 @@ mod tests {
     #[test]
     fn test_clear() {
         let mut vec = svec![1, -2, 3];
+        assert_eq!(vec.capacity(), 3);
         vec.clear();
         assert!(vec.is_empty());
-        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.capacity(), 3);
     }
     #[test]
     fn test_count() {
@@ -2575,6 +5371,330 @@ mod tests {
         assert_eq!(vec.count(Sign::Minus), 2);
     }
 
+    #[test]
+    fn test_zero() {
+        let mut vec = svec![0, 1, -1, 0, 2];
+        assert_eq!(vec.count(Sign::Zero), 2);
+        assert_eq!(vec.count_zero(), 2);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 3]);
+        assert_eq!(vec.values(Sign::Zero).collect::<Vec<_>>(), vec![&0, &0]);
+
+        let mut rng = WyRand::new();
+        for _ in 0..20 {
+            let idx = vec.random(Sign::Zero, &mut rng).unwrap();
+            assert_eq!(vec[idx], 0);
+        }
+
+        // Moving elements in and out of the zero bucket.
+        vec.set(1, 0);
+        vec.set(0, -3);
+        assert_eq!(vec.indices(Sign::Zero), &set![1, 3]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Plus), &set![4]);
+
+        // Structural changes keep the zero set in sync.
+        vec.insert(0, 0);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 2, 4]);
+        vec.remove(1);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 1, 3]);
+        vec.retain(|&x| x != 2);
+        assert_eq!(vec.as_slice(), &[0, 0, -1, 0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 1, 3]);
+        assert_eq!(vec.swap_remove(0), 0);
+        assert_eq!(vec.indices(Sign::Zero), &set![0, 1]);
+        assert_eq!(vec.pop(), Some(-1));
+        assert_eq!(vec.count(Sign::Zero), 2);
+        assert_eq!(vec.count(Sign::Minus), 0);
+        vec.clear();
+        assert_eq!(vec.count(Sign::Zero), 0);
+    }
+
+    #[test]
+    fn test_zero_floats() {
+        let vec = svec![0.0, -0.0, 1.5, -2.5];
+        assert_eq!(vec.count(Sign::Zero), 2);
+        assert_eq!(vec.count(Sign::Plus), 1);
+        assert_eq!(vec.count(Sign::Minus), 1);
+    }
+
+    #[test]
+    fn test_sign_bit() {
+        let mut vec = svec![SignBit(0.0), SignBit(-0.0), SignBit(2.0)];
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert_eq!(vec.count(Sign::Zero), 0);
+
+        vec.set(0, SignBit(-0.0));
+        vec.set(1, SignBit(0.0));
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+
+        vec.push(SignBit(f64::NAN));
+        assert_eq!(vec.indices_nan(), &set![3]);
+        vec.set_nan_policy(NanPolicy::SignBit).unwrap();
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_classifier() {
+        fn parity(x: &i32) -> Sign {
+            if x % 2 == 0 {
+                Sign::Plus
+            } else {
+                Sign::Minus
+            }
+        }
+
+        let mut vec = SignVec::with_classifier(parity);
+        vec.extend_from_slice(&[1, 2, 3, -4, 0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 3, 4]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+
+        vec.set(0, 6);
+        vec.insert(1, 7);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2, 4, 5]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 3]);
+
+        let tail = vec.split_off(3);
+        assert_eq!(tail.indices(Sign::Plus), &set![1, 2]);
+        assert_eq!(tail.indices(Sign::Minus), &set![0]);
+
+        vec.set_classifier(i32::sign);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1, 2]);
+        assert_eq!(vec.count(Sign::Minus), 0);
+    }
+
+    #[test]
+    fn test_classifier_by_field() {
+        let mut vec = SignVec::with_classifier(|a: &Account| {
+            if a.balance() > 100 {
+                Sign::Plus
+            } else {
+                Sign::Minus
+            }
+        });
+        vec.push(Account::new(50));
+        vec.push(Account::new(500));
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut vec = svec![1.0, -1.0, 0.0];
+        vec.set_hysteresis(Some(0.5));
+        assert_eq!(vec.hysteresis(), Some(&0.5));
+
+        // Oscillating inside the band never moves an index.
+        for v in [0.3, -0.3, 0.1, -0.49] {
+            vec.set(0, v);
+            vec.set(1, -v);
+            vec.set(2, v);
+        }
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert_eq!(vec.indices(Sign::Zero), &set![2]);
+
+        vec.set(0, -0.5);
+        vec.set(1, 0.7);
+        vec.set(2, 0.5);
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+
+        // NaNs leave the band logic alone.
+        vec.set(0, f64::NAN);
+        vec.set(0, 0.1);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1, 2]);
+
+        vec.set(1, 0.0);
+        assert_eq!(vec.count(Sign::Zero), 0);
+        vec.set_hysteresis(None);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        assert_eq!(vec.hysteresis(), None);
+    }
+
+    #[test]
+    fn test_std_signables() {
+        use std::cmp::Reverse;
+        use std::num::{NonZeroI32, Saturating, Wrapping};
+        use std::rc::Rc;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let vec = svec![Wrapping(-3), Wrapping(0), Wrapping(i32::MAX)];
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        let vec = svec![Saturating(-1i8), Saturating(1i8)];
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+
+        let vec: SignVec<_> = [-4, 7]
+            .iter()
+            .map(|&v| NonZeroI32::new(v).unwrap())
+            .collect();
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+
+        let vec = svec![Reverse(2), Reverse(-2), Reverse(0)];
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![2]);
+
+        let vec = svec![1.cmp(&2), 2.cmp(&2), 3.cmp(&2)];
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![2]);
+
+        let vec = svec![Duration::ZERO, Duration::from_millis(5)];
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1]);
+        assert_eq!(vec.count(Sign::Zero), 0);
+
+        let vec = svec![Some(-1.0), None, Some(f64::NAN)];
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        assert_eq!(vec.indices_nan(), &set![2]);
+        let vec = svec![NoneAsPlus(Some(-1.0)), NoneAsPlus(None), None.into()];
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        let vec = svec![
+            NoneAsMinus(None),
+            NoneAsMinus(Some(0)),
+            NoneAsMinus(Some(4))
+        ];
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![2]);
+        assert_eq!(vec[0].into_inner(), None);
+
+        let vec = svec![Box::new(-1), Box::new(1)];
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        let vec = svec![Rc::new(0.0), Rc::new(f64::NAN)];
+        assert_eq!(vec.indices(Sign::Zero), &set![0]);
+        assert_eq!(vec.indices_nan(), &set![1]);
+        let vec = svec![Arc::new(5i64)];
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+
+        let values = [3, -3];
+        let vec: SignVec<&i32> = values.iter().collect();
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+    }
+
+    #[test]
+    fn test_sign_algebra() {
+        use std::collections::BTreeSet;
+
+        let signs = [Sign::Plus, Sign::Minus, Sign::Zero];
+        for a in signs {
+            assert_eq!(-(-a), a);
+            assert_eq!(!a, -a);
+            assert_eq!(a * Sign::Plus, a);
+            assert_eq!(a * Sign::Zero, Sign::Zero);
+            for b in signs {
+                assert_eq!(a * b, b * a);
+                assert_eq!(i8::from(a * b), i8::from(a) * i8::from(b));
+                assert_eq!(a.cmp(&b), i8::from(a).cmp(&i8::from(b)));
+            }
+            assert_eq!(a.to_string().parse::<Sign>(), Ok(a));
+            assert_eq!(Sign::of(&a.apply(2.5)), a);
+        }
+
+        let mut sign = Sign::Minus;
+        sign *= Sign::Minus;
+        assert_eq!(sign, Sign::Plus);
+        assert_eq!(Sign::from(false), Sign::Minus);
+        assert_eq!(Sign::default(), Sign::Zero);
+        assert!("plus".parse::<Sign>().is_err());
+
+        let sorted: Vec<_> = signs
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(sorted, vec![Sign::Minus, Sign::Zero, Sign::Plus]);
+        let unique: HashSet<_> = [Sign::Plus, Sign::Plus].into_iter().collect();
+        assert_eq!(unique.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_front() {
+        let mut vec = svec![1, 2, 3];
+        assert_eq!(vec.remove(0), 1);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1]);
+    }
+
+    #[test]
+    fn test_nan_track() {
+        let mut vec = svec![1.0, f64::NAN, -1.0, -f64::NAN];
+        assert_eq!(vec.count(Sign::Plus), 1);
+        assert_eq!(vec.count(Sign::Minus), 1);
+        assert_eq!(vec.count_nan(), 2);
+        assert_eq!(vec.indices_nan(), &set![1, 3]);
+        assert_eq!(vec.indices(Sign::Minus), &set![2]);
+
+        vec.set(1, -2.0);
+        assert_eq!(vec.indices_nan(), &set![3]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 2]);
+
+        vec.swap_remove(0);
+        assert_eq!(vec.indices_nan(), &set![0]);
+        vec.remove(0);
+        assert_eq!(vec.count_nan(), 0);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 1]);
+    }
+
+    #[test]
+    fn test_nan_sign_bit() {
+        let mut vec = SignVec::with_nan_policy(NanPolicy::SignBit);
+        vec.extend_from_slice(&[f64::NAN, -f64::NAN, 0.0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert_eq!(vec.count(Sign::Zero), 1);
+        assert_eq!(vec.count_nan(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN rejected")]
+    fn test_nan_reject_push() {
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.push(f32::NAN);
+    }
+
+    #[test]
+    fn test_nan_reject_try() {
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        vec.try_extend_from_slice(&[1.0, -1.0]).unwrap();
+        assert_eq!(
+            vec.try_extend_from_slice(&[2.0, f32::NAN]),
+            Err(SignVecError::NanRejected { index: 3 })
+        );
+        assert_eq!(
+            vec.try_push(f32::NAN),
+            Err(SignVecError::NanRejected { index: 2 })
+        );
+        assert_eq!(
+            vec.try_set(1, f32::NAN),
+            Err(SignVecError::NanRejected { index: 1 })
+        );
+        assert_eq!(vec.as_slice(), &[1.0, -1.0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+    }
+
+    #[test]
+    fn test_set_nan_policy() {
+        let mut vec = svec![f64::NAN, -f64::NAN, 2.0];
+        assert_eq!(
+            vec.set_nan_policy(NanPolicy::Reject),
+            Err(SignVecError::NanRejected { index: 0 })
+        );
+        assert_eq!(vec.nan_policy(), NanPolicy::Track);
+
+        vec.set_nan_policy(NanPolicy::SignBit).unwrap();
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+
+        vec.set_nan_policy(NanPolicy::Track).unwrap();
+        assert_eq!(vec.indices_nan(), &set![0, 1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![2]);
+    }
+
     #[test]
     fn test_dedup() {
         // Test deduplication of positive elements
@@ -2590,20 +5710,55 @@ mod tests {
 
     #[test]
     fn test_dedup_by() {
-        // Test deduplication using a custom equality function
-        let mut vec = svec![10, -5, 10, -5];
+        // Test deduplication using a custom equality function; only consecutive elements are
+        // compared.
+        let mut vec = svec![10, 10, -5, 10, -5, -5];
         vec.dedup_by(|a, b| a == b);
-        assert_eq!(vec.as_slice(), &[10, -5]);
+        assert_eq!(vec.as_slice(), &[10, -5, 10, -5]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 3]);
+
+        // Each element is compared with the last one kept, not the one just removed.
+        let mut vec = svec![1, 2, 3, 4, 8, 9];
+        vec.dedup_by(|a, b| a - b < 3);
+        assert_eq!(vec.as_slice(), &[1, 4, 8]);
 
         // Test deduplication of complex objects based on a specific property
         let mut vec = svec![
             Account::new(100),
-            Account::new(-50),
             Account::new(100),
             Account::new(-50),
+            Account::new(100),
         ];
         vec.dedup_by(|a, b| a.balance() == b.balance());
-        assert_eq!(vec.as_slice().len(), 2);
+        assert_eq!(vec.as_slice().len(), 3);
+    }
+
+    #[test]
+    fn test_dedup_keeps_buckets() {
+        // Elements held in their bucket by the hysteresis band keep it when others are removed.
+        let mut vec = svec![1.0, -1.0, -1.0, 2.0];
+        vec.set_hysteresis(Some(0.5));
+        vec.set(1, 0.25);
+        vec.set(2, 0.25);
+        vec.set(3, -0.25);
+        vec.dedup();
+        assert_eq!(vec.as_slice(), &[1.0, 0.25, -0.25]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+
+        // A panicking predicate leaves the vector consistent.
+        let mut vec = svec![3, 3, -1, -1, 2];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.dedup_by(|a, b| {
+                assert!(*a != 2);
+                a == b
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &[3, -1, 2]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
     }
 
     #[test]
@@ -2645,6 +5800,57 @@ mod tests {
         assert_eq!(drained_elements, vec![1, 2, 3]);
         assert_eq!(vec.as_slice(), &[4, 5]);
     }
+
+    #[test]
+    fn test_drain_double_ended() {
+        let mut vec = svec![1, -2, 0, 3, -4, 5, -6];
+        let mut drain = vec.drain(1..6);
+        assert_eq!(drain.len(), 5);
+        assert_eq!(drain.next_back(), Some(5));
+        assert_eq!(drain.next(), Some(-2));
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.rev().collect::<Vec<_>>(), vec![-4, 3, 0]);
+        assert_eq!(vec.as_slice(), &[1, -6]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert!(vec.indices(Sign::Zero).is_empty());
+    }
+
+    #[test]
+    fn test_drain_dropped_or_leaked() {
+        use std::rc::Rc;
+
+        let shared = Rc::new(-1);
+        let mut vec: SignVec<Rc<i32>> = (0..6)
+            .map(|i| {
+                if i % 2 == 0 {
+                    Rc::new(i)
+                } else {
+                    shared.clone()
+                }
+            })
+            .collect();
+        assert_eq!(Rc::strong_count(&shared), 4);
+
+        // Dropping early drops the elements that were not yielded.
+        let mut drain = vec.drain(1..4);
+        assert_eq!(drain.next().as_deref(), Some(&-1));
+        drop(drain);
+        assert_eq!(Rc::strong_count(&shared), 2);
+        assert_eq!(vec.iter().map(|x| **x).collect::<Vec<_>>(), vec![0, 4, -1]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(vec.indices(Sign::Zero), &set![0]);
+        assert_eq!(vec.indices(Sign::Minus), &set![2]);
+
+        // Leaking leaks the drained elements but leaves the vector consistent.
+        std::mem::forget(vec.drain(..2));
+        assert_eq!(vec.len(), 1);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+        assert_eq!(vec.count(Sign::Plus) + vec.count(Sign::Zero), 0);
+        vec.push(Rc::new(7));
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(Rc::strong_count(&shared), 2);
+    }
     #[test]
     fn test_extend_from_slice() {
         let mut vec = svec![];
@@ -2669,11 +5875,59 @@ mod tests {
         assert_eq!(vec.count(Sign::Plus), 4);
     }
 
+    #[test]
+    fn test_insert_remove_shift() {
+        let mut vec = svec![1.0, -2.0, 0.0, f64::NAN, 3.0];
+        vec.insert(0, -1.0);
+        vec.insert(3, f64::NAN);
+        vec.insert(7, 0.0);
+        assert_eq!(vec.indices(Sign::Plus), &set![1, 6]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Zero), &set![4, 7]);
+        assert_eq!(vec.indices_nan(), &set![3, 5]);
+
+        assert_eq!(vec.remove(0), -1.0);
+        assert!(vec.remove(2).is_nan());
+        assert_eq!(vec.remove(5), 0.0);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 4]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1]);
+        assert_eq!(vec.indices(Sign::Zero), &set![2]);
+        assert_eq!(vec.indices_nan(), &set![3]);
+    }
+
+    #[test]
+    fn test_signvec32() {
+        let values = [1.0, -2.0, 0.0, f64::NAN, 3.0, -4.0];
+        let mut wide: SignVec<f64> = values.iter().collect();
+        let mut compact: SignVec32<f64> = values.iter().collect();
+        wide.insert(2, -5.0);
+        compact.insert(2, -5.0);
+        wide.set(0, -1.0);
+        compact.set(0, -1.0);
+        assert_eq!(wide.remove(5), compact.remove(5));
+        wide.drain(1..3);
+        compact.drain(1..3);
+        compact.push(2.0);
+        wide.push(2.0);
+
+        assert_eq!(compact.len(), 5);
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(compact.indices(sign), &Set::from(wide.indices(sign)));
+        }
+        assert_eq!(compact.indices_nan(), &set![2]);
+        assert_eq!(compact.try_push(-3.0), Ok(()));
+        assert_eq!(compact.count(Sign::Minus), 3);
+        assert_eq!(
+            SignVecError::LengthLimitExceeded { max_len: 4 }.to_string(),
+            "Length limit of the index backend exceeded: 4"
+        );
+    }
+
     #[test]
     fn test_indices() {
         let vec = svec![1, -2, 3, -4, 5];
-        assert_eq!(vec.indices(Sign::Plus), &Set::from(vec![0, 2, 4]));
-        assert_eq!(vec.indices(Sign::Minus), &Set::from(vec![1, 3]));
+        assert_eq!(vec.indices(Sign::Plus), &IndexSet::from(vec![0, 2, 4]));
+        assert_eq!(vec.indices(Sign::Minus), &IndexSet::from(vec![1, 3]));
     }
 
     #[test]
@@ -2768,8 +6022,11 @@ mod tests {
         let mut vec = svec![1, -2, 3];
         vec.resize(5, 0);
         assert_eq!(vec.as_slice(), &[1, -2, 3, 0, 0]);
-        assert_eq!(vec.count(Sign::Plus), 4);
+        assert_eq!(vec.count(Sign::Plus), 2);
         assert_eq!(vec.count(Sign::Minus), 1);
+        assert_eq!(vec.count(Sign::Zero), 2);
+        vec.resize(4, 0);
+        assert_eq!(vec.indices(Sign::Zero), &set![3]);
     }
 
     #[test]
@@ -2799,6 +6056,82 @@ mod tests {
         assert_eq!(vec.count(Sign::Minus), 0);
     }
 
+    #[test]
+    fn test_retain_single_pass() {
+        use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn counted(x: &i32) -> Sign {
+            CALLS.fetch_add(1, Relaxed);
+            x.sign()
+        }
+
+        let mut vec = SignVec::with_classifier(counted);
+        vec.extend([4, -1, 0, 7, -3, 0, 2]);
+        CALLS.store(0, Relaxed);
+        vec.retain(|&x| x != 0 && x != 7);
+        assert_eq!(CALLS.load(Relaxed), 0);
+        assert_eq!(vec.as_slice(), &[4, -1, -3, 2]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 3]);
+        assert_eq!(vec.indices(Sign::Minus), &set![1, 2]);
+        assert!(vec.indices(Sign::Zero).is_empty());
+
+        // retain_mut classifies the elements it keeps again.
+        vec.retain_mut(|x| {
+            *x = -*x;
+            *x != 1
+        });
+        assert_eq!(CALLS.load(Relaxed), 3);
+        assert_eq!(vec.as_slice(), &[-4, 3, -2]);
+        assert_eq!(vec.indices(Sign::Plus), &set![1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![0, 2]);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut vec = svec![1.0, -2.0, f64::NAN, 0.0, -5.0, 6.0, -7.0];
+        let extracted: Vec<_> = vec.extract_if(1..6, |x| *x < 0.0 || x.is_nan()).collect();
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(vec.as_slice(), &[1.0, 0.0, 6.0, -7.0]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 2]);
+        assert_eq!(vec.indices(Sign::Zero), &set![1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![3]);
+        assert!(vec.indices_nan().is_empty());
+
+        // Stopping early keeps the elements not visited yet.
+        let mut vec = svec![-1, 2, -3, 4, -5];
+        let mut iter = vec.extract_if(.., |x| *x < 0);
+        assert_eq!(iter.next(), Some(-1));
+        assert_eq!(iter.next(), Some(-3));
+        drop(iter);
+        assert_eq!(vec.as_slice(), &[2, 4, -5]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1]);
+        assert_eq!(vec.indices(Sign::Minus), &set![2]);
+
+        // A leaked iterator leaves the vector empty and consistent.
+        std::mem::forget(vec.extract_if(.., |_| true));
+        assert!(vec.is_empty());
+        assert_eq!(vec.count(Sign::Plus) + vec.count(Sign::Minus), 0);
+        vec.push(-8);
+        assert_eq!(vec.indices(Sign::Minus), &set![0]);
+    }
+
+    #[test]
+    fn test_extract_if_panic() {
+        let mut vec = svec![-1, 2, -3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.extract_if(.., |x| {
+                assert!(*x != 4);
+                *x < 0
+            })
+            .for_each(drop);
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &[2, 4]);
+        assert_eq!(vec.indices(Sign::Plus), &set![0, 1]);
+        assert!(vec.indices(Sign::Minus).is_empty());
+    }
+
     #[test]
     fn test_random() {
         let mut svec = svec![1, -1, 2, -2, 3];
@@ -2838,8 +6171,9 @@ mod tests {
             vec.set_len(5);
         }
         assert_eq!(vec.as_slice(), &[1, -2, 3, 0, 0]);
-        assert_eq!(vec.count(Sign::Plus), 4);
+        assert_eq!(vec.count(Sign::Plus), 2);
         assert_eq!(vec.count(Sign::Minus), 1);
+        assert_eq!(vec.count(Sign::Zero), 2);
 
         unsafe {
             vec.set_len(2);
@@ -2847,6 +6181,7 @@ mod tests {
         assert_eq!(vec.as_slice(), &[1, -2]);
         assert_eq!(vec.count(Sign::Plus), 1);
         assert_eq!(vec.count(Sign::Minus), 1);
+        assert_eq!(vec.count(Sign::Zero), 0);
     }
 
     #[test]
@@ -2941,6 +6276,78 @@ mod tests {
         assert!(vec2.indices(Sign::Minus).contains(&0));
     }
 
+    #[test]
+    fn test_sync_chunks() {
+        fn check<T: Signable + Clone + Copy>(vals: &[T]) {
+            let mut vec: SignVec<T> = SignVec::new();
+            vec.extend_from_slice(&vals[..70]);
+            vec.extend_from_slice(&vals[70..]);
+            for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+                let expected: Set = (0..vals.len())
+                    .filter(|&i| !vals[i].is_nan() && vals[i].sign() == sign)
+                    .collect();
+                assert_eq!(vec.indices(sign), &expected);
+            }
+            let nans: Set = (0..vals.len()).filter(|&i| vals[i].is_nan()).collect();
+            assert_eq!(vec.indices_nan(), &nans);
+            let before = vec.clone();
+            vec.sync();
+            for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+                assert_eq!(vec.indices(sign), before.indices(sign));
+            }
+        }
+
+        // 150 elements: two full chunks of 64 and a short one, entered off the chunk grid.
+        let ints: Vec<i32> = (0..150).map(|i| (i * 37 % 11) - 5).collect();
+        check(&ints);
+        let floats: Vec<f64> = ints
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| match i % 13 {
+                3 => -0.0,
+                7 => f64::NAN,
+                11 => -f64::NAN,
+                _ => x as f64 / 4.0,
+            })
+            .collect();
+        check(&floats);
+        check(&floats.iter().map(|&x| x as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sync_chunks_nan_policy() {
+        let vals: Vec<f32> = (0..100)
+            .map(|i| match i % 5 {
+                0 => f32::NAN,
+                1 => -f32::NAN,
+                2 => 1.0,
+                3 => -1.0,
+                _ => 0.0,
+            })
+            .collect();
+        let mut vec = SignVec::with_nan_policy(NanPolicy::SignBit);
+        vec.extend_from_slice(&vals);
+        assert_eq!(vec.count(Sign::Plus), 40);
+        assert_eq!(vec.count(Sign::Minus), 40);
+        assert_eq!(vec.count(Sign::Zero), 20);
+        assert_eq!(vec.count_nan(), 0);
+        assert!(vec.indices(Sign::Minus).contains(&81));
+
+        // A rejected NaN stops the extension at the start of its chunk, with the sets in step.
+        let mut clean: Vec<f32> = vals.iter().copied().filter(|x| !x.is_nan()).collect();
+        clean.extend([1.0, 1.0, -1.0, 0.0, 1.0, f32::NAN, -1.0]);
+        let mut vec = SignVec::with_nan_policy(NanPolicy::Reject);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.extend_from_slice(&clean);
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.as_slice(), &clean[..64]);
+        let fresh = SignVec::from(&clean[..64]);
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(vec.indices(sign), fresh.indices(sign));
+        }
+    }
+
     #[test]
     fn test_truncate() {
         let mut vec = svec![1, -2, 3];
@@ -2999,7 +6406,7 @@ mod tests {
         assert_eq!(vec.count(Sign::Plus), 3);
         assert_eq!(vec.count(Sign::Minus), 2);
 
-        let plus_indices: Vec<usize> = vec.indices(Sign::Plus).iter().copied().collect();
+        let plus_indices: Vec<usize> = vec.indices(Sign::Plus).iter().collect();
         assert_eq!(plus_indices, vec![0, 2, 4]);
 
         let minus_values: Vec<&i32> = vec.values(Sign::Minus).collect();
@@ -3132,7 +6539,8 @@ mod tests {
     // Tests for Extend<&T> for SignVec<T>
     #[test]
     fn test_extend_ref() {
-        let mut sign_vec = SignVec::default();
+        // `SignVec<&i32>` is an `Extend<&i32>` too, so the element type must be spelled out.
+        let mut sign_vec: SignVec<i32> = SignVec::default();
         sign_vec.extend(&[1, -2, 3]);
         assert_eq!(sign_vec.vals, vec![1, -2, 3]);
         // Additional checks can verify the correct state of pos and neg.
@@ -3190,6 +6598,21 @@ mod tests {
         let vec = vec![1, -2, 3];
         let sign_vec = SignVec::from(vec);
         assert_eq!(sign_vec.vals, vec![1, -2, 3]);
+
+        // The elements are filed in bulk, across several chunks, as `push` would file them.
+        let vals: Vec<f64> = (0..200)
+            .map(|i| [1.0, -1.0, 0.0, f64::NAN][i % 4])
+            .collect();
+        let sign_vec = SignVec::from(vals.clone());
+        let mut pushed = SignVec::new();
+        for &val in &vals {
+            pushed.push(val);
+        }
+        for sign in [Sign::Plus, Sign::Minus, Sign::Zero] {
+            assert_eq!(sign_vec.indices(sign), pushed.indices(sign));
+        }
+        assert_eq!(sign_vec.indices_nan(), pushed.indices_nan());
+        assert_eq!(sign_vec.count_nan(), 50);
     }
 
     // Tests for From<[T; N]> for SignVec<T>
@@ -3237,10 +6660,14 @@ mod tests {
     #[test]
     fn into_iterator_mut_ref() {
         let mut sign_vec = SignVec::from(&[1, -1, 2, -2][..]);
-        let mut collected: Vec<&mut i32> = (&mut sign_vec).into_iter().collect();
+        let mut collected: Vec<SignVecMut<i32>> = (&mut sign_vec).into_iter().collect();
         // Perform a mutation as a demonstration.
-        collected.iter_mut().for_each(|x| **x *= 2);
-        assert_eq!(sign_vec.vals, vec![2, -2, 4, -4]);
+        collected.iter_mut().for_each(|x| **x *= -2);
+        assert_eq!(collected[3].index(), 3);
+        drop(collected);
+        assert_eq!(sign_vec.vals, vec![-2, 2, -4, 4]);
+        assert_eq!(sign_vec.indices(Sign::Plus), &set![1, 3]);
+        assert_eq!(sign_vec.indices(Sign::Minus), &set![0, 2]);
     }
     // Test for Index trait implementation
     #[test]
@@ -0,0 +1,667 @@
+use crate::{IndexBackend, IndexSet, Sign};
+use nanorand::WyRand;
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+/// A vector-like data structure classifying its elements against a movable threshold.
+///
+/// Elements above the threshold are `Sign::Plus`, elements below it `Sign::Minus` and elements
+/// equal to it `Sign::Zero`. Besides the three index sets, `ThresholdSignVec` keeps the indices
+/// ordered by value, so that `set_threshold` only visits the elements lying between the old and
+/// the new threshold instead of reclassifying the whole vector.
+///
+/// The price is paid on writes: `push`, `pop`, `set` and `swap_remove` locate the element in
+/// the ordering with a binary search and shift the ordering to make room, which is O(n) in the
+/// worst case.
+///
+/// # Panics
+///
+/// Every operation classifying a value panics if the value is not comparable with the
+/// threshold, as is the case for floating-point NaNs.
+///
+/// # Type Parameters
+///
+/// * `T`: The type of elements stored in the `ThresholdSignVec`.
+/// * `S`: The `IndexBackend` storing the index sets, `IndexSet` by default.
+///
+/// # Examples
+///
+/// ```
+/// use signvec::{Sign, ThresholdSignVec};
+///
+/// let mut tv = ThresholdSignVec::from_vec(10.0, vec![5.0, 12.0, 10.0, 30.0]);
+/// assert_eq!(tv.count(Sign::Plus), 2);
+///
+/// // Only the elements between 10 and 20 are visited.
+/// assert_eq!(tv.set_threshold(20.0), 2);
+/// assert_eq!(tv.count(Sign::Plus), 1);
+/// assert_eq!(tv.count(Sign::Minus), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThresholdSignVec<T, S = IndexSet>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    vals: Vec<T>,
+    pos: S,
+    neg: S,
+    zero: S,
+    sorted: Vec<usize>,
+    threshold: T,
+}
+
+impl<T, S> ThresholdSignVec<T, S>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    /// Returns a slice containing all elements in the `ThresholdSignVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::ThresholdSignVec;
+    ///
+    /// let tv = ThresholdSignVec::from_vec(1, vec![0, 2]);
+    ///
+    /// assert_eq!(tv.as_slice(), &[0, 2]);
+    /// ```
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[T] {
+        self.vals.as_slice()
+    }
+
+    /// Clears the `ThresholdSignVec`, keeping its threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::from_vec(1, vec![0, 2]);
+    /// tv.clear();
+    ///
+    /// assert!(tv.is_empty());
+    /// assert_eq!(tv.count(Sign::Plus), 0);
+    /// ```
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.vals.clear();
+        self.pos.clear();
+        self.neg.clear();
+        self.zero.clear();
+        self.sorted.clear();
+    }
+
+    /// Returns the number of elements classified as `sign` against the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let tv = ThresholdSignVec::from_vec(1, vec![0, 1, 2, 3]);
+    ///
+    /// assert_eq!(tv.count(Sign::Plus), 2);
+    /// assert_eq!(tv.count(Sign::Zero), 1);
+    /// ```
+    #[inline(always)]
+    pub fn count(&self, sign: Sign) -> usize {
+        self.indices(sign).len()
+    }
+
+    /// Returns a reference to the set of indices of elements classified as `sign`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{IndexSet, Sign, ThresholdSignVec};
+    ///
+    /// let tv = ThresholdSignVec::from_vec(1, vec![0, 1, 2]);
+    ///
+    /// assert_eq!(tv.indices(Sign::Minus), &IndexSet::from([0]));
+    /// ```
+    #[inline(always)]
+    pub fn indices(&self, sign: Sign) -> &S {
+        match sign {
+            Sign::Plus => &self.pos,
+            Sign::Minus => &self.neg,
+            Sign::Zero => &self.zero,
+        }
+    }
+
+    /// Removes the last element and returns it, or `None` if the `ThresholdSignVec` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::from_vec(1, vec![0, 2]);
+    ///
+    /// assert_eq!(tv.pop(), Some(2));
+    /// assert_eq!(tv.count(Sign::Plus), 0);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let idx = self.vals.len().checked_sub(1)?;
+        self.unlink(idx);
+        let sign = self.sign_of(idx);
+        self.set_of(sign).remove(&idx);
+        self.vals.pop()
+    }
+
+    /// Appends an element to the back of the `ThresholdSignVec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is not comparable with the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::new(1);
+    /// tv.push(3);
+    ///
+    /// assert_eq!(tv.count(Sign::Plus), 1);
+    /// ```
+    pub fn push(&mut self, element: T) {
+        let sign = self.classify(&element);
+        let idx = self.vals.len();
+        self.vals.push(element);
+        self.set_of(sign).insert(idx);
+        self.link(idx);
+    }
+
+    /// Returns a random index of an element classified as `sign`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sign`: The classification of the element to pick.
+    /// * `rng`: A mutable reference to a random number generator implementing the `WyRand` trait.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    /// use nanorand::WyRand;
+    ///
+    /// let tv = ThresholdSignVec::from_vec(1, vec![0, 2]);
+    /// let mut rng = WyRand::new();
+    ///
+    /// assert_eq!(tv.random(Sign::Plus, &mut rng), Some(1));
+    /// ```
+    #[inline(always)]
+    pub fn random(&self, sign: Sign, rng: &mut WyRand) -> Option<usize> {
+        self.indices(sign).random(rng)
+    }
+
+    /// Sets the value at the specified index, updating its classification and its position in
+    /// the ordering.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or the value is not comparable with the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::from_vec(1, vec![0, 2]);
+    /// tv.set(0, 5);
+    ///
+    /// assert_eq!(tv.count(Sign::Plus), 2);
+    /// ```
+    pub fn set(&mut self, idx: usize, val: T) {
+        if idx >= self.vals.len() {
+            panic!(
+                "Index out of bounds: index {} length {}",
+                idx,
+                self.vals.len()
+            );
+        }
+        let new_sign = self.classify(&val);
+        let old_sign = self.sign_of(idx);
+        self.unlink(idx);
+        self.vals[idx] = val;
+        if old_sign != new_sign {
+            self.set_of(old_sign).remove(&idx);
+            self.set_of(new_sign).insert(idx);
+        }
+        self.link(idx);
+    }
+
+    /// Moves the threshold to `threshold`, reclassifying the affected elements.
+    ///
+    /// Only the elements whose value lies between the old and the new threshold, both included,
+    /// are visited. They are found by binary search in the ordering, so the cost is
+    /// O(log n + k) for k visited elements, instead of the O(n) of a full resync.
+    ///
+    /// Returns the number of elements whose classification changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is not comparable with the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::from_vec(0, vec![-5, -1, 1, 3, 8]);
+    ///
+    /// assert_eq!(tv.set_threshold(3), 2);
+    /// assert_eq!(tv.count(Sign::Minus), 3);
+    /// assert_eq!(tv.count(Sign::Zero), 1);
+    /// assert_eq!(tv.count(Sign::Plus), 1);
+    /// ```
+    pub fn set_threshold(&mut self, threshold: T) -> usize {
+        let (low, high) = match threshold.partial_cmp(&self.threshold) {
+            Some(Ordering::Less) => (threshold.clone(), self.threshold.clone()),
+            Some(_) => (self.threshold.clone(), threshold.clone()),
+            None => panic!("Threshold is not comparable with the current one"),
+        };
+        self.threshold = threshold;
+
+        let vals = &self.vals;
+        let start = self.sorted.partition_point(|&i| vals[i] < low);
+        let end = self.sorted.partition_point(|&i| vals[i] <= high);
+        let mut flips = 0;
+        for pos in start..end {
+            let idx = self.sorted[pos];
+            let old_sign = self.sign_of(idx);
+            let new_sign = self.classify(&self.vals[idx]);
+            if old_sign != new_sign {
+                self.set_of(old_sign).remove(&idx);
+                self.set_of(new_sign).insert(idx);
+                flips += 1;
+            }
+        }
+        flips
+    }
+
+    /// Removes an element by swapping it with the last element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{IndexSet, Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::from_vec(1, vec![0, 2, 3]);
+    ///
+    /// assert_eq!(tv.swap_remove(0), 0);
+    /// assert_eq!(tv.indices(Sign::Plus), &IndexSet::from([0, 1]));
+    /// ```
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let last = self.vals.len() - 1;
+        if index > last {
+            panic!(
+                "Index out of bounds: index {} length {}",
+                index,
+                self.vals.len()
+            );
+        }
+        self.unlink(index);
+        let sign = self.sign_of(index);
+        self.set_of(sign).remove(&index);
+        if index < last {
+            self.unlink(last);
+            let moved_sign = self.sign_of(last);
+            self.set_of(moved_sign).remove(&last);
+            self.set_of(moved_sign).insert(index);
+        }
+        let removed = self.vals.swap_remove(index);
+        if index < last {
+            self.link(index);
+        }
+        removed
+    }
+
+    /// Returns the threshold the elements are classified against.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::ThresholdSignVec;
+    ///
+    /// let tv = ThresholdSignVec::<i32>::new(7);
+    ///
+    /// assert_eq!(tv.threshold(), &7);
+    /// ```
+    #[inline(always)]
+    pub fn threshold(&self) -> &T {
+        &self.threshold
+    }
+
+    /// Returns an iterator over the values classified as `sign`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let tv = ThresholdSignVec::from_vec(1, vec![0, 2, 3]);
+    ///
+    /// assert_eq!(tv.values(Sign::Plus).collect::<Vec<_>>(), vec![&2, &3]);
+    /// ```
+    #[inline(always)]
+    pub fn values(&self, sign: Sign) -> impl Iterator<Item = &T> + '_ {
+        self.indices(sign).iter().map(move |idx| &self.vals[idx])
+    }
+
+    /// Creates a new empty `ThresholdSignVec` storing its index sets in the backend `S`, with
+    /// room for `capacity` elements.
+    ///
+    /// The other constructors use the default `IndexSet` backend. A `ThresholdSignVec` with a
+    /// different backend starts from here and is filled with `push` or `extend`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{BitmapIndex, Sign, ThresholdSignVec};
+    ///
+    /// let mut tv = ThresholdSignVec::<i32, BitmapIndex>::with_backend(1, 10);
+    /// tv.extend([0, 2, 3]);
+    ///
+    /// assert_eq!(tv.count(Sign::Plus), 2);
+    /// ```
+    #[inline(always)]
+    pub fn with_backend(threshold: T, capacity: usize) -> Self {
+        ThresholdSignVec {
+            vals: Vec::with_capacity(capacity),
+            pos: S::with_max(capacity),
+            neg: S::with_max(capacity),
+            zero: S::with_max(capacity),
+            sorted: Vec::with_capacity(capacity),
+            threshold,
+        }
+    }
+}
+
+impl<T, S> ThresholdSignVec<T, S>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    /// Classifies `val` against the threshold.
+    #[inline(always)]
+    fn classify(&self, val: &T) -> Sign {
+        match val.partial_cmp(&self.threshold) {
+            Some(Ordering::Greater) => Sign::Plus,
+            Some(Ordering::Less) => Sign::Minus,
+            Some(Ordering::Equal) => Sign::Zero,
+            None => panic!("Value is not comparable with the threshold"),
+        }
+    }
+
+    /// Orders two indices by their value, breaking ties by index.
+    #[inline(always)]
+    fn order(vals: &[T], a: usize, b: usize) -> Ordering {
+        vals[a]
+            .partial_cmp(&vals[b])
+            .expect("Values are comparable")
+            .then(a.cmp(&b))
+    }
+
+    /// Returns the position `idx` has, or would have, in the ordering.
+    #[inline(always)]
+    fn rank(&self, idx: usize) -> usize {
+        let vals = &self.vals;
+        self.sorted
+            .partition_point(|&i| Self::order(vals, i, idx) == Ordering::Less)
+    }
+
+    /// Inserts `idx` in the ordering according to its current value.
+    #[inline(always)]
+    fn link(&mut self, idx: usize) {
+        let rank = self.rank(idx);
+        self.sorted.insert(rank, idx);
+    }
+
+    /// Removes `idx` from the ordering. Must be called before its value changes.
+    #[inline(always)]
+    fn unlink(&mut self, idx: usize) {
+        let rank = self.rank(idx);
+        self.sorted.remove(rank);
+    }
+
+    /// Returns the classification `idx` is currently filed under.
+    #[inline(always)]
+    fn sign_of(&self, idx: usize) -> Sign {
+        if self.pos.contains(&idx) {
+            Sign::Plus
+        } else if self.neg.contains(&idx) {
+            Sign::Minus
+        } else {
+            Sign::Zero
+        }
+    }
+
+    #[inline(always)]
+    fn set_of(&mut self, sign: Sign) -> &mut S {
+        match sign {
+            Sign::Plus => &mut self.pos,
+            Sign::Minus => &mut self.neg,
+            Sign::Zero => &mut self.zero,
+        }
+    }
+}
+
+impl<T> ThresholdSignVec<T>
+where
+    T: PartialOrd + Clone,
+{
+    /// Creates a `ThresholdSignVec` from a vector, classifying its elements against `threshold`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is not comparable with the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::{Sign, ThresholdSignVec};
+    ///
+    /// let tv = ThresholdSignVec::from_vec(0.5, vec![0.2, 0.9]);
+    ///
+    /// assert_eq!(tv.count(Sign::Minus), 1);
+    /// ```
+    pub fn from_vec(threshold: T, vals: Vec<T>) -> Self {
+        let mut threshold_vec = Self::with_capacity(threshold, vals.len());
+        threshold_vec.extend(vals);
+        threshold_vec
+    }
+
+    /// Creates a new empty `ThresholdSignVec` classifying against `threshold`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::ThresholdSignVec;
+    ///
+    /// let tv = ThresholdSignVec::<f64>::new(0.5);
+    ///
+    /// assert!(tv.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn new(threshold: T) -> Self {
+        Self::with_backend(threshold, 0)
+    }
+
+    /// Creates a new empty `ThresholdSignVec` with the specified capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use signvec::ThresholdSignVec;
+    ///
+    /// let tv = ThresholdSignVec::<f32>::with_capacity(0.0, 10);
+    ///
+    /// assert!(tv.is_empty());
+    /// ```
+    #[inline(always)]
+    pub fn with_capacity(threshold: T, capacity: usize) -> Self {
+        Self::with_backend(threshold, capacity)
+    }
+}
+
+impl<T, S> Extend<T> for ThresholdSignVec<T, S>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    /// Appends the elements of `iter`, classifying them first and placing them in the ordering
+    /// with a single sort.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is not comparable with the threshold, in which case none of the
+    /// elements is appended.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let vals: Vec<T> = iter.into_iter().collect();
+        let signs: Vec<Sign> = vals.iter().map(|val| self.classify(val)).collect();
+        let start = self.vals.len();
+        for (i, sign) in signs.into_iter().enumerate() {
+            self.set_of(sign).insert(start + i);
+        }
+        self.vals.extend(vals);
+        self.sorted.extend(start..self.vals.len());
+        let vals = &self.vals;
+        self.sorted
+            .sort_unstable_by(|&a, &b| Self::order(vals, a, b));
+    }
+}
+
+impl<T, S> Deref for ThresholdSignVec<T, S>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    type Target = [T];
+
+    /// Dereferences the `ThresholdSignVec` to a slice of its values.
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.vals
+    }
+}
+
+impl<T, S> From<ThresholdSignVec<T, S>> for Vec<T>
+where
+    T: PartialOrd + Clone,
+    S: IndexBackend,
+{
+    /// Converts a `ThresholdSignVec` into a `Vec`, moving its elements.
+    fn from(threshold_vec: ThresholdSignVec<T, S>) -> Self {
+        threshold_vec.vals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitmapIndex;
+    use fastset::{set, Set};
+
+    /// Checks the sets and the ordering against a from-scratch rebuild.
+    fn assert_consistent<T: PartialOrd + Clone + std::fmt::Debug>(tv: &ThresholdSignVec<T>) {
+        let fresh = ThresholdSignVec::from_vec(tv.threshold.clone(), tv.vals.clone());
+        assert_eq!(tv.pos, fresh.pos);
+        assert_eq!(tv.neg, fresh.neg);
+        assert_eq!(tv.zero, fresh.zero);
+        assert_eq!(tv.sorted, fresh.sorted);
+    }
+
+    #[test]
+    fn test_set_threshold_up_and_down() {
+        let mut tv = ThresholdSignVec::from_vec(0, vec![4, -2, 0, 7, 2, 2, -9]);
+        assert_eq!(tv.indices(Sign::Plus), &set![0, 3, 4, 5]);
+        assert_eq!(tv.indices(Sign::Zero), &set![2]);
+
+        assert_eq!(tv.set_threshold(2), 3);
+        assert_eq!(tv.indices(Sign::Plus), &set![0, 3]);
+        assert_eq!(tv.indices(Sign::Zero), &set![4, 5]);
+        assert_eq!(tv.indices(Sign::Minus), &set![1, 2, 6]);
+        assert_consistent(&tv);
+
+        assert_eq!(tv.set_threshold(-5), 4);
+        assert_eq!(tv.indices(Sign::Plus), &set![0, 1, 2, 3, 4, 5]);
+        assert_eq!(tv.indices(Sign::Minus), &set![6]);
+        assert_consistent(&tv);
+
+        assert_eq!(tv.set_threshold(-5), 0);
+    }
+
+    #[test]
+    fn test_mutations_keep_order() {
+        let mut tv = ThresholdSignVec::new(1.0);
+        for v in [3.0, -1.0, 1.0, 0.5, 3.0, 2.0] {
+            tv.push(v);
+        }
+        assert_consistent(&tv);
+        tv.set(0, -4.0);
+        tv.set(2, 1.5);
+        assert_consistent(&tv);
+        assert_eq!(tv.swap_remove(1), -1.0);
+        assert_consistent(&tv);
+        assert_eq!(tv.pop(), Some(3.0));
+        assert_consistent(&tv);
+        tv.set_threshold(1.5);
+        assert_consistent(&tv);
+        assert_eq!(tv.indices(Sign::Zero), &set![2]);
+        tv.clear();
+        assert_eq!(tv.pop(), None);
+    }
+
+    #[test]
+    fn test_bitmap_backend() {
+        let mut tv = ThresholdSignVec::<i32, BitmapIndex>::with_backend(0, 4);
+        tv.extend([4, -2, 0, 7]);
+        tv.push(2);
+        assert_eq!(
+            tv.indices(Sign::Plus).iter().collect::<Vec<_>>(),
+            vec![0, 3, 4]
+        );
+        assert_eq!(tv.set_threshold(3), 2);
+        assert_eq!(
+            tv.indices(Sign::Minus).iter().collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+        assert_eq!(tv.swap_remove(1), -2);
+        assert_eq!(
+            tv.indices(Sign::Minus).iter().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(tv.sorted, vec![2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn test_extend_rejects_all_on_panic() {
+        let mut tv = ThresholdSignVec::from_vec(1.0, vec![0.0, 2.0]);
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| tv.extend([3.0, f64::NAN])));
+        assert!(result.is_err());
+        assert_eq!(tv.as_slice(), &[0.0, 2.0]);
+        assert_consistent(&tv);
+        tv.extend([1.0, -1.0]);
+        assert_eq!(tv.indices(Sign::Zero), &set![2]);
+        assert_consistent(&tv);
+    }
+
+    #[test]
+    #[should_panic(expected = "not comparable")]
+    fn test_nan_panics() {
+        let mut tv = ThresholdSignVec::new(0.0);
+        tv.push(f64::NAN);
+    }
+}
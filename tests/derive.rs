@@ -0,0 +1,83 @@
+#![cfg(feature = "derive")]
+
+use signvec::{svec, Sign, SignVec, Signable};
+
+#[derive(Clone, Debug, PartialEq, Signable)]
+#[signable(field = "balance")]
+struct Account {
+    id: u32,
+    balance: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Signable)]
+struct Energy(f64);
+
+#[derive(Clone, Debug, PartialEq, Signable)]
+#[signable(field = "1")]
+struct Reading(&'static str, f32);
+
+#[derive(Clone, Debug, PartialEq, Signable)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Signable)]
+enum Spin {
+    #[signable(plus)]
+    Up,
+    #[signable(minus)]
+    Down,
+    #[signable(zero)]
+    Vacancy,
+    #[signable(plus)]
+    Pinned { _site: usize },
+    #[signable(minus)]
+    Frozen(u8),
+}
+
+#[test]
+fn struct_field() {
+    let accounts = svec![
+        Account {
+            id: 1,
+            balance: 100
+        },
+        Account {
+            id: 2,
+            balance: -50
+        },
+        Account { id: 3, balance: 0 },
+    ];
+    assert_eq!(accounts.count(Sign::Plus), 1);
+    assert_eq!(accounts.count(Sign::Minus), 1);
+    assert_eq!(accounts.count(Sign::Zero), 1);
+    assert_eq!(accounts.values(Sign::Minus).next().unwrap().id, 2);
+}
+
+#[test]
+fn single_field_and_tuple_index() {
+    assert_eq!(Energy(-1.5).sign(), Sign::Minus);
+    assert!(Energy(f64::NAN).is_nan());
+    assert_eq!(Reading("probe", 2.0).sign(), Sign::Plus);
+    assert_eq!(Wrapper { inner: -3i8 }.sign(), Sign::Minus);
+
+    let energies = svec![Energy(1.0), Energy(f64::NAN)];
+    assert_eq!(energies.count_nan(), 1);
+}
+
+#[test]
+fn enum_variants() {
+    let mut spins = svec![
+        Spin::Up,
+        Spin::Down,
+        Spin::Vacancy,
+        Spin::Pinned { _site: 4 },
+        Spin::Frozen(1),
+    ];
+    assert_eq!(spins.count(Sign::Plus), 2);
+    assert_eq!(spins.count(Sign::Minus), 2);
+    assert_eq!(spins.count(Sign::Zero), 1);
+
+    spins.set(0, Spin::Down);
+    assert_eq!(spins.count(Sign::Minus), 3);
+}